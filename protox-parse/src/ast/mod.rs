@@ -1,3 +1,12 @@
+//! The syntax tree of a protobuf source file, as returned by [`parse_ast()`](crate::parse_ast).
+//!
+//! The tree closely follows the structure of the source file, and keeps the span and comments of each definition.
+//! No semantic checks have been performed on it, so it may contain undefined type names, duplicate field numbers
+//! and other errors which would be reported when converting it to a
+//! [`FileDescriptorProto`](prost_types::FileDescriptorProto).
+//!
+//! All spans are byte offsets into the source file.
+
 use std::{
     convert::TryFrom,
     fmt::{self, Write},
@@ -8,309 +17,536 @@ use prost_types::field_descriptor_proto;
 
 use crate::{join_span, Span};
 
-#[derive(Default, Copy, Clone, Debug, PartialEq)]
-pub(crate) enum Syntax {
+/// The syntax of a protobuf source file.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Syntax {
+    /// The `proto2` syntax. This is used for files which do not have a `syntax` statement.
     #[default]
     Proto2,
+    /// The `proto3` syntax.
     Proto3,
 }
 
+/// A protobuf source file.
 #[derive(Default, Clone, Debug, PartialEq)]
-pub(crate) struct File {
+#[non_exhaustive]
+pub struct File {
+    /// The span of the whole file.
     pub span: Span,
+    /// The syntax of the file.
+    ///
+    /// If the file has no `syntax` statement, this is [`Syntax::Proto2`].
     pub syntax: Syntax,
+    /// The span and comments of the `syntax` statement, if the file has one.
     pub syntax_span: std::option::Option<(Span, Comments)>,
+    /// The `package` statement, if the file has one.
     pub package: std::option::Option<Package>,
+    /// The `import` statements of the file.
     pub imports: Vec<Import>,
+    /// The file-level `option` statements.
     pub options: Vec<Option>,
+    /// The top-level definitions in the file, in the order they appear.
     pub items: Vec<FileItem>,
 }
 
+/// A top-level definition in a file.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) enum FileItem {
+#[non_exhaustive]
+pub enum FileItem {
+    /// An `enum` definition.
     Enum(Enum),
+    /// A `message` definition.
     Message(Message),
+    /// An `extend` block.
     Extend(Extend),
+    /// A `service` definition.
     Service(Service),
 }
 
+/// The comments attached to a definition.
 #[derive(Clone, Default, Debug, PartialEq)]
-pub(crate) struct Comments {
+#[non_exhaustive]
+pub struct Comments {
+    /// Comments before the definition which are separated from it by a blank line.
     pub leading_detached_comments: Vec<std::string::String>,
+    /// The comment directly before the definition.
     pub leading_comment: std::option::Option<std::string::String>,
+    /// The comment directly after the definition.
     pub trailing_comment: std::option::Option<std::string::String>,
 }
 
+/// A single identifier.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Ident {
+#[non_exhaustive]
+pub struct Ident {
+    /// The text of the identifier.
     pub value: std::string::String,
+    /// The span of the identifier.
     pub span: Span,
 }
 
+/// A dot-separated sequence of identifiers, such as a package name.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct FullIdent {
+#[non_exhaustive]
+pub struct FullIdent {
+    /// The identifiers, which always contain at least one element.
     pub parts: Vec<Ident>,
 }
 
+/// A reference to a message or enum type.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct TypeName {
+#[non_exhaustive]
+pub struct TypeName {
+    /// The span of the leading `.`, if the name is fully-qualified.
     pub leading_dot: std::option::Option<Span>,
+    /// The name of the type.
     pub name: FullIdent,
 }
 
+/// An integer literal.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Int {
+#[non_exhaustive]
+pub struct Int {
+    /// Whether the literal is preceded by a `-` sign.
     pub negative: bool,
+    /// The absolute value of the literal.
     pub value: u64,
+    /// The span of the literal, including any sign.
     pub span: Span,
 }
 
+/// A floating-point literal.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Float {
+#[non_exhaustive]
+pub struct Float {
+    /// The value of the literal.
     pub value: f64,
+    /// The span of the literal, including any sign.
     pub span: Span,
 }
 
+/// A string literal.
 #[derive(Clone, PartialEq)]
-pub(crate) struct String {
+#[non_exhaustive]
+pub struct String {
+    /// The value of the literal, with escapes resolved. This may not be valid UTF-8.
     pub value: Vec<u8>,
+    /// The span of the literal, including quotes.
     pub span: Span,
 }
 
+/// The value of an option.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) enum OptionValue {
+#[non_exhaustive]
+pub enum OptionValue {
+    /// An identifier, such as an enum value name or `true`.
     Ident {
+        /// Whether the identifier is preceded by a `-` sign, as in `-inf`.
         negative: bool,
+        /// The identifier.
         ident: Ident,
+        /// The span of the value, including any sign.
         span: Span,
     },
+    /// An integer literal.
     Int(Int),
+    /// A floating-point literal.
     Float(Float),
+    /// A string literal.
     String(String),
+    /// A message value in the protobuf text format, with its span.
     Aggregate(std::string::String, Span),
 }
 
+/// An `import` statement.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Import {
+#[non_exhaustive]
+pub struct Import {
+    /// The `weak` or `public` modifier of the import and its span, if present.
     pub kind: std::option::Option<(ImportKind, Span)>,
+    /// The name of the imported file.
     pub value: std::string::String,
+    /// The span of the imported file name.
     pub value_span: Span,
+    /// The comments attached to the statement.
     pub comments: Comments,
+    /// The span of the statement.
     pub span: Span,
 }
 
+/// The modifier of an `import` statement.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) enum ImportKind {
+#[non_exhaustive]
+pub enum ImportKind {
+    /// A `weak` import.
     Weak,
+    /// A `public` import.
     Public,
 }
 
+/// A `package` statement.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Package {
+#[non_exhaustive]
+pub struct Package {
+    /// The package name.
     pub name: FullIdent,
+    /// The comments attached to the statement.
     pub comments: Comments,
+    /// The span of the statement.
     pub span: Span,
 }
 
+/// An `option` statement.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Option {
+#[non_exhaustive]
+pub struct Option {
+    /// The name and value of the option.
     pub body: OptionBody,
+    /// The comments attached to the statement.
     pub comments: Comments,
+    /// The span of the statement.
     pub span: Span,
 }
 
+/// A component of an option name.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) enum OptionNamePart {
+#[non_exhaustive]
+pub enum OptionNamePart {
+    /// A plain identifier, such as `java_package`.
     Ident(Ident),
+    /// A parenthesized extension name, such as `(my.custom_option)`, with the span including the parentheses.
     Extension(TypeName, Span),
 }
 
+/// The name and value of an option.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct OptionBody {
+#[non_exhaustive]
+pub struct OptionBody {
+    /// The dot-separated components of the option name.
     pub name: Vec<OptionNamePart>,
+    /// The value of the option.
     pub value: OptionValue,
 }
 
+/// A bracketed list of options, such as those set on a field.
 #[derive(Clone, Default, Debug, PartialEq)]
-pub(crate) struct OptionList {
+#[non_exhaustive]
+pub struct OptionList {
+    /// The options in the list.
     pub options: Vec<OptionBody>,
+    /// The span of the list, including the brackets.
     pub span: Span,
 }
 
+/// A `message` definition.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Message {
+#[non_exhaustive]
+pub struct Message {
+    /// The name of the message.
     pub name: Ident,
+    /// The contents of the message.
     pub body: MessageBody,
+    /// The comments attached to the definition.
     pub comments: Comments,
+    /// The span of the definition.
     pub span: Span,
 }
 
+/// A field of a message, oneof or extend block.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Field {
+#[non_exhaustive]
+pub struct Field {
+    /// The label of the field and its span, if present.
     pub label: std::option::Option<(FieldLabel, Span)>,
+    /// The name of the field.
     pub name: Ident,
+    /// The type of the field.
     pub kind: FieldKind,
+    /// The field number.
     pub number: Int,
+    /// The options set on the field, if any.
     pub options: std::option::Option<OptionList>,
+    /// The comments attached to the field.
     pub comments: Comments,
+    /// The span of the field.
     pub span: Span,
 }
 
+/// The label of a field.
 #[derive(Copy, Clone, Debug, PartialEq)]
-pub(crate) enum FieldLabel {
+#[non_exhaustive]
+pub enum FieldLabel {
+    /// An `optional` field.
     Optional = 1,
+    /// A `required` field.
     Required = 2,
+    /// A `repeated` field.
     Repeated = 3,
 }
 
+/// The contents of a message or group.
 #[derive(Debug, Default, Clone, PartialEq)]
-pub(crate) struct MessageBody {
+#[non_exhaustive]
+pub struct MessageBody {
+    /// The fields and nested definitions of the message, in the order they appear.
     pub items: Vec<MessageItem>,
+    /// The `extensions` statements of the message.
     pub extensions: Vec<Extensions>,
+    /// The `option` statements of the message.
     pub options: Vec<Option>,
+    /// The `reserved` statements of the message.
     pub reserved: Vec<Reserved>,
 }
 
+/// A field or nested definition in a message.
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) enum MessageItem {
+#[non_exhaustive]
+pub enum MessageItem {
+    /// A field.
     Field(Field),
+    /// A nested `enum` definition.
     Enum(Enum),
+    /// A nested `message` definition.
     Message(Message),
+    /// A nested `extend` block.
     Extend(Extend),
+    /// A `oneof` definition.
     Oneof(Oneof),
 }
 
+/// The type of a field.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) enum FieldKind {
+#[non_exhaustive]
+pub enum FieldKind {
+    /// A field with a scalar, message or enum type.
     Normal {
+        /// The type of the field.
         ty: Ty,
+        /// The span of the type.
         ty_span: Span,
     },
+    /// A group field, which defines a nested message with the same name.
     Group {
+        /// The span of the `group` keyword.
         ty_span: Span,
+        /// The contents of the group message.
         body: MessageBody,
     },
+    /// A map field.
     Map {
+        /// The span of the whole `map<K, V>` type.
         ty_span: Span,
+        /// The key type of the map.
         key_ty: Ty,
+        /// The span of the key type.
         key_ty_span: Span,
+        /// The value type of the map.
         value_ty: Ty,
+        /// The span of the value type.
         value_ty_span: Span,
     },
 }
 
+/// A field type.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) enum Ty {
+#[non_exhaustive]
+pub enum Ty {
+    /// The `double` type.
     Double,
+    /// The `float` type.
     Float,
+    /// The `int32` type.
     Int32,
+    /// The `int64` type.
     Int64,
+    /// The `uint32` type.
     Uint32,
+    /// The `uint64` type.
     Uint64,
+    /// The `sint32` type.
     Sint32,
+    /// The `sint64` type.
     Sint64,
+    /// The `fixed32` type.
     Fixed32,
+    /// The `fixed64` type.
     Fixed64,
+    /// The `sfixed32` type.
     Sfixed32,
+    /// The `sfixed64` type.
     Sfixed64,
+    /// The `bool` type.
     Bool,
+    /// The `string` type.
     String,
+    /// The `bytes` type.
     Bytes,
+    /// A message or enum type.
     Named(TypeName),
 }
 
+/// A `oneof` definition.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Oneof {
+#[non_exhaustive]
+pub struct Oneof {
+    /// The name of the oneof.
     pub name: Ident,
+    /// The `option` statements of the oneof.
     pub options: Vec<Option>,
+    /// The fields of the oneof.
     pub fields: Vec<Field>,
+    /// The comments attached to the definition.
     pub comments: Comments,
+    /// The span of the definition.
     pub span: Span,
 }
 
+/// An `extend` block.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Extend {
+#[non_exhaustive]
+pub struct Extend {
+    /// The name of the extended message.
     pub extendee: TypeName,
+    /// The extension fields defined in the block.
     pub fields: Vec<Field>,
+    /// The comments attached to the block.
     pub comments: Comments,
+    /// The span of the block.
     pub span: Span,
 }
 
+/// A `reserved` statement.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Reserved {
+#[non_exhaustive]
+pub struct Reserved {
+    /// The numbers or names which are reserved.
     pub kind: ReservedKind,
+    /// The comments attached to the statement.
     pub comments: Comments,
+    /// The span of the statement.
     pub span: Span,
 }
 
+/// An `extensions` statement.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Extensions {
+#[non_exhaustive]
+pub struct Extensions {
+    /// The field number ranges reserved for extensions.
     pub ranges: Vec<ReservedRange>,
+    /// The options set on the ranges, if any.
     pub options: std::option::Option<OptionList>,
+    /// The comments attached to the statement.
     pub comments: Comments,
+    /// The span of the statement.
     pub span: Span,
 }
 
+/// The contents of a `reserved` statement.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) enum ReservedKind {
+#[non_exhaustive]
+pub enum ReservedKind {
+    /// Reserved number ranges.
     Ranges(Vec<ReservedRange>),
+    /// Reserved names.
     Names(Vec<Ident>),
 }
 
+/// A range of numbers in a `reserved` or `extensions` statement.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct ReservedRange {
+#[non_exhaustive]
+pub struct ReservedRange {
+    /// The first number in the range.
     pub start: Int,
+    /// The end of the range.
     pub end: ReservedRangeEnd,
 }
 
+/// The end of a [`ReservedRange`].
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) enum ReservedRangeEnd {
+#[non_exhaustive]
+pub enum ReservedRangeEnd {
+    /// The range contains a single number.
     None,
+    /// The range ends at the given number, inclusive.
     Int(Int),
+    /// The range ends at the largest allowed number, using the `max` keyword with the given span.
     Max(Span),
 }
 
+/// An `enum` definition.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Enum {
+#[non_exhaustive]
+pub struct Enum {
+    /// The name of the enum.
     pub name: Ident,
+    /// The `option` statements of the enum.
     pub options: Vec<Option>,
+    /// The values of the enum.
     pub values: Vec<EnumValue>,
+    /// The `reserved` statements of the enum.
     pub reserved: Vec<Reserved>,
+    /// The comments attached to the definition.
     pub comments: Comments,
+    /// The span of the definition.
     pub span: Span,
 }
 
+/// A value of an enum.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct EnumValue {
+#[non_exhaustive]
+pub struct EnumValue {
+    /// The name of the value.
     pub name: Ident,
+    /// The number of the value.
     pub number: Int,
+    /// The options set on the value, if any.
     pub options: std::option::Option<OptionList>,
+    /// The comments attached to the value.
     pub comments: Comments,
+    /// The span of the value.
     pub span: Span,
 }
 
+/// A `service` definition.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Service {
+#[non_exhaustive]
+pub struct Service {
+    /// The name of the service.
     pub name: Ident,
+    /// The `option` statements of the service.
     pub options: Vec<Option>,
+    /// The methods of the service.
     pub methods: Vec<Method>,
+    /// The comments attached to the definition.
     pub comments: Comments,
+    /// The span of the definition.
     pub span: Span,
 }
 
+/// An `rpc` method of a service.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Method {
+#[non_exhaustive]
+pub struct Method {
+    /// The name of the method.
     pub name: Ident,
+    /// The input message type of the method.
     pub input_ty: TypeName,
+    /// The output message type of the method.
     pub output_ty: TypeName,
+    /// The `option` statements of the method.
     pub options: Vec<Option>,
+    /// The span of the `stream` keyword on the input type, if the method is client streaming.
     pub client_streaming: std::option::Option<Span>,
+    /// The span of the `stream` keyword on the output type, if the method is server streaming.
     pub server_streaming: std::option::Option<Span>,
+    /// The comments attached to the definition.
     pub comments: Comments,
+    /// The span of the definition.
     pub span: Span,
 }
 
 impl Int {
+    /// Gets the value of the literal as an `i32`, if it is in range.
     pub fn as_i32(&self) -> std::option::Option<i32> {
         if self.negative {
             if self.value == (i32::MAX as u64 + 1) {
@@ -323,6 +559,7 @@ impl Int {
         }
     }
 
+    /// Gets the value of the literal as an `i64`, if it is in range.
     pub fn as_i64(&self) -> std::option::Option<i64> {
         if self.negative {
             if self.value == (i64::MAX as u64 + 1) {
@@ -335,6 +572,7 @@ impl Int {
         }
     }
 
+    /// Gets the value of the literal as a `u32`, if it is in range.
     pub fn as_u32(&self) -> std::option::Option<u32> {
         if self.negative {
             None
@@ -343,6 +581,7 @@ impl Int {
         }
     }
 
+    /// Gets the value of the literal as a `u64`, if it is not negative.
     pub fn as_u64(&self) -> std::option::Option<u64> {
         if self.negative {
             None
@@ -353,7 +592,7 @@ impl Int {
 }
 
 impl String {
-    pub fn into_utf8(self) -> Result<(std::string::String, Span), Self> {
+    pub(crate) fn into_utf8(self) -> Result<(std::string::String, Span), Self> {
         match std::string::String::from_utf8(self.value) {
             Ok(string) => Ok((string, self.span)),
             Err(err) => Err(String {
@@ -365,7 +604,7 @@ impl String {
 }
 
 impl Ident {
-    pub fn new(value: impl Into<std::string::String>, span: Range<usize>) -> Self {
+    pub(crate) fn new(value: impl Into<std::string::String>, span: Range<usize>) -> Self {
         Ident {
             span,
             value: value.into(),
@@ -374,12 +613,14 @@ impl Ident {
 }
 
 impl FullIdent {
+    /// Gets the span of the whole name.
     pub fn span(&self) -> Span {
         self.parts.first().unwrap().span.start..self.parts.last().unwrap().span.end
     }
 }
 
 impl TypeName {
+    /// Gets the span of the whole name, including any leading `.`.
     pub fn span(&self) -> Span {
         if let Some(leading_dot) = &self.leading_dot {
             join_span(leading_dot.clone(), self.name.span())
@@ -390,7 +631,7 @@ impl TypeName {
 }
 
 impl Ty {
-    pub fn proto_ty(&self) -> std::option::Option<field_descriptor_proto::Type> {
+    pub(crate) fn proto_ty(&self) -> std::option::Option<field_descriptor_proto::Type> {
         match self {
             Ty::Double => Some(field_descriptor_proto::Type::Double),
             Ty::Float => Some(field_descriptor_proto::Type::Float),
@@ -411,7 +652,7 @@ impl Ty {
         }
     }
 
-    pub fn ty_name(&self) -> std::option::Option<std::string::String> {
+    pub(crate) fn ty_name(&self) -> std::option::Option<std::string::String> {
         match self {
             Ty::Named(name) => Some(name.to_string()),
             _ => None,
@@ -420,6 +661,7 @@ impl Ty {
 }
 
 impl OptionNamePart {
+    /// Gets the span of this part of the name.
     pub fn span(&self) -> Span {
         match self {
             OptionNamePart::Ident(ident) => ident.span.clone(),
@@ -429,10 +671,11 @@ impl OptionNamePart {
 }
 
 impl OptionBody {
-    pub fn has_name(&self, name: &str) -> bool {
+    pub(crate) fn has_name(&self, name: &str) -> bool {
         matches!(self.name.as_slice(), [OptionNamePart::Ident(ident)] if ident.value == name)
     }
 
+    /// Gets the span of the option name.
     pub fn name_span(&self) -> Span {
         debug_assert!(!self.name.is_empty());
         join_span(
@@ -441,12 +684,14 @@ impl OptionBody {
         )
     }
 
+    /// Gets the span of the option name and value.
     pub fn span(&self) -> Span {
         join_span(self.name_span(), self.value.span())
     }
 }
 
 impl OptionValue {
+    /// Gets the span of the value.
     pub fn span(&self) -> Span {
         match self {
             OptionValue::Ident { span, .. } => span.clone(),
@@ -457,14 +702,14 @@ impl OptionValue {
         }
     }
 
-    pub fn to_token_string(&self) -> std::string::String {
+    pub(crate) fn to_token_string(&self) -> std::string::String {
         match self {
             OptionValue::String(s) => format!("\"{}\"", s),
             _ => self.to_string(),
         }
     }
 
-    pub fn as_f64(&self) -> std::option::Option<f64> {
+    pub(crate) fn as_f64(&self) -> std::option::Option<f64> {
         match self {
             OptionValue::Ident {
                 negative, ident, ..
@@ -495,7 +740,7 @@ impl OptionValue {
         }
     }
 
-    pub fn as_bool(&self) -> std::option::Option<bool> {
+    pub(crate) fn as_bool(&self) -> std::option::Option<bool> {
         match self {
             OptionValue::Ident {
                 negative: false,
@@ -513,10 +758,12 @@ impl OptionValue {
 }
 
 impl ReservedRange {
+    /// Gets the span of the first number in the range.
     pub fn start_span(&self) -> Span {
         self.start.span.clone()
     }
 
+    /// Gets the span of the end of the range, which is the start if the range contains a single number.
     pub fn end_span(&self) -> Span {
         match &self.end {
             ReservedRangeEnd::None => self.start.span.clone(),
@@ -525,6 +772,7 @@ impl ReservedRange {
         }
     }
 
+    /// Gets the span of the whole range.
     pub fn span(&self) -> Span {
         join_span(self.start_span(), self.end_span())
     }
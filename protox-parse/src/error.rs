@@ -62,12 +62,33 @@ pub(crate) enum ParseErrorKind {
         #[label("defined here")]
         span: Span,
     },
+    #[error("editions are not supported")]
+    #[diagnostic(help("use 'syntax = \"proto2\";' or 'syntax = \"proto3\";' instead"))]
+    EditionsUnsupported {
+        #[label("defined here")]
+        span: Span,
+    },
+    #[error("missing syntax statement")]
+    #[diagnostic(help(
+        "add 'syntax = \"proto3\";' or 'syntax = \"proto2\";' to the start of the file"
+    ))]
+    MissingSyntax {
+        #[label("expected syntax here")]
+        span: Span,
+    },
     #[error("invalid identifier")]
     #[diagnostic(help("identifiers must consist of letters, numbers and underscores, and may not start with a number"))]
     InvalidIdentifier {
         #[label("defined here")]
         span: Span,
     },
+    #[error("reserved names must be string literals")]
+    #[diagnostic(help("use '\"{name}\"' instead, only editions allow identifiers here"))]
+    ReservedNameIdentifier {
+        name: String,
+        #[label("found here")]
+        span: Span,
+    },
     #[error("invalid group name")]
     #[diagnostic(help(
         "group names must consist of a capital letter followed by letters, numbers and underscores"
@@ -91,6 +112,17 @@ pub(crate) enum ParseErrorKind {
         #[label("…and again here")]
         second: Span,
     },
+    #[error("map entry message '{name}' is generated by multiple fields")]
+    #[diagnostic(help(
+        "rename one of the fields so that their names differ after conversion to PascalCase"
+    ))]
+    MapEntryNameClash {
+        name: String,
+        #[label("first generated here")]
+        first: Span,
+        #[label("generated again here")]
+        second: Span,
+    },
     #[error("whitespace is required between an integer literal and an identifier")]
     NoSpaceBetweenIntAndIdent {
         #[label("found here")]
@@ -125,6 +157,12 @@ pub(crate) enum ParseErrorKind {
         #[label("defined here")]
         span: Span,
     },
+    #[error("extension range numbers must be between 1 and {max}")]
+    ExtensionRangeOutOfBounds {
+        max: i32,
+        #[label("defined here")]
+        span: Span,
+    },
     #[error("enum numbers must be between {} and {}", i32::MIN, i32::MAX)]
     InvalidEnumNumber {
         #[label("defined here")]
@@ -147,6 +185,11 @@ pub(crate) enum ParseErrorKind {
         #[label("defined here")]
         span: Span,
     },
+    #[error("the 'json_name' option is not allowed on extension fields")]
+    InvalidExtendFieldJsonName {
+        #[label("defined here")]
+        span: Span,
+    },
     #[error("extension fields may not be required")]
     RequiredExtendField {
         #[label("defined here")]
@@ -188,6 +231,44 @@ pub(crate) enum ParseErrorKind {
         #[label("defined here")]
         span: Span,
     },
+    #[error("a file cannot import itself")]
+    SelfImport {
+        #[label("imported here")]
+        span: Span,
+    },
+    #[error("name '{name}' is reserved")]
+    ReservedNameUsed {
+        name: String,
+        #[label("reserved here")]
+        reserved: Span,
+        #[label("used here")]
+        span: Span,
+    },
+    #[error("enum number '{number}' conflicts with reserved range '{range}'")]
+    EnumReservedNumberUsed {
+        number: i32,
+        range: String,
+        #[label("reserved here")]
+        reserved: Span,
+        #[label("used here")]
+        span: Span,
+    },
+    #[error("messages are nested too deeply")]
+    #[diagnostic(help("the maximum nesting depth is 100"))]
+    NestingTooDeep {
+        #[label("defined here")]
+        span: Span,
+    },
+    #[error("services can only be defined at file scope")]
+    ServiceNotAtFileScope {
+        #[label("defined here")]
+        span: Span,
+    },
+    #[error("a map field key or value type cannot be a group")]
+    InvalidMapFieldGroupType {
+        #[label("group defined here")]
+        span: Span,
+    },
     #[error("expected value to be {expected}, but found '{actual}'")]
     ValueInvalidType {
         expected: String,
@@ -248,10 +329,14 @@ impl ParseError {
             ParseErrorKind::InvalidUtf8String { span } => Some(span.clone()),
             ParseErrorKind::NestedBlockComment { span } => Some(span.clone()),
             ParseErrorKind::UnknownSyntax { span, .. } => Some(span.clone()),
+            ParseErrorKind::EditionsUnsupported { span } => Some(span.clone()),
+            ParseErrorKind::MissingSyntax { span } => Some(span.clone()),
             ParseErrorKind::InvalidIdentifier { span } => Some(span.clone()),
+            ParseErrorKind::ReservedNameIdentifier { span, .. } => Some(span.clone()),
             ParseErrorKind::InvalidGroupName { span } => Some(span.clone()),
             ParseErrorKind::InvalidImport { span } => Some(span.clone()),
             ParseErrorKind::DuplicatePackage { .. } => None,
+            ParseErrorKind::MapEntryNameClash { second, .. } => Some(second.clone()),
             ParseErrorKind::NoSpaceBetweenIntAndIdent { span } => Some(span.clone()),
             ParseErrorKind::HashCommentOutsideTextFormat { span } => Some(span.clone()),
             ParseErrorKind::FloatSuffixOutsideTextFormat { span } => Some(span.clone()),
@@ -259,10 +344,12 @@ impl ParseError {
             ParseErrorKind::UnexpectedEof { .. } => None,
             ParseErrorKind::NegativeIdentOutsideDefault { span } => Some(span.clone()),
             ParseErrorKind::InvalidMessageNumber { span } => Some(span.clone()),
+            ParseErrorKind::ExtensionRangeOutOfBounds { span, .. } => Some(span.clone()),
             ParseErrorKind::InvalidEnumNumber { span } => Some(span.clone()),
             ParseErrorKind::InvalidDefault { span, .. } => Some(span.clone()),
             ParseErrorKind::Proto3DefaultValue { span } => Some(span.clone()),
             ParseErrorKind::InvalidExtendFieldKind { span, .. } => Some(span.clone()),
+            ParseErrorKind::InvalidExtendFieldJsonName { span } => Some(span.clone()),
             ParseErrorKind::RequiredExtendField { span } => Some(span.clone()),
             ParseErrorKind::MapFieldWithLabel { span } => Some(span.clone()),
             ParseErrorKind::OneofFieldWithLabel { span } => Some(span.clone()),
@@ -271,6 +358,12 @@ impl ParseError {
             ParseErrorKind::Proto3RequiredField { span } => Some(span.clone()),
             ParseErrorKind::InvalidOneofFieldKind { span, .. } => Some(span.clone()),
             ParseErrorKind::InvalidMapFieldKeyType { span } => Some(span.clone()),
+            ParseErrorKind::InvalidMapFieldGroupType { span } => Some(span.clone()),
+            ParseErrorKind::ServiceNotAtFileScope { span } => Some(span.clone()),
+            ParseErrorKind::NestingTooDeep { span } => Some(span.clone()),
+            ParseErrorKind::ReservedNameUsed { span, .. } => Some(span.clone()),
+            ParseErrorKind::EnumReservedNumberUsed { span, .. } => Some(span.clone()),
+            ParseErrorKind::SelfImport { span } => Some(span.clone()),
             ParseErrorKind::ValueInvalidType { span, .. } => Some(span.clone()),
             ParseErrorKind::IntegerValueOutOfRange { span, .. } => Some(span.clone()),
             ParseErrorKind::EmptyOneof { span } => Some(span.clone()),
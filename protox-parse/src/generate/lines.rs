@@ -25,6 +25,26 @@ impl LineResolver {
         }
     }
 
+    pub fn offset(&self, line: i32, col: i32) -> Option<usize> {
+        let line_start = match usize::try_from(line).ok()? {
+            0 => 0,
+            line => *self.lines.get(line - 1)?,
+        };
+        usize::try_from(line_start.checked_add(col)?).ok()
+    }
+
+    pub fn offset_span(&self, span: &[i32]) -> Option<Span> {
+        let (start_line, start_col, end_line, end_col) = match *span {
+            [start_line, start_col, end_col] => (start_line, start_col, start_line, end_col),
+            [start_line, start_col, end_line, end_col] => {
+                (start_line, start_col, end_line, end_col)
+            }
+            _ => return None,
+        };
+
+        Some(self.offset(start_line, start_col)?..self.offset(end_line, end_col)?)
+    }
+
     pub fn resolve_span(&self, span: Span) -> Vec<i32> {
         let (start_line, start_col) = self.resolve(span.start);
         let (end_line, end_col) = self.resolve(span.end);
@@ -67,3 +87,20 @@ fn resolve_line_number() {
     assert_eq!(resolver.resolve(9), (3, 2));
     assert_eq!(resolver.resolve(10), (3, 3));
 }
+
+#[test]
+fn resolve_offset() {
+    let resolver = LineResolver::new("f\n\noo\r\nbar");
+
+    for offset in 0..=10 {
+        let (line, col) = resolver.resolve(offset);
+        assert_eq!(resolver.offset(line, col), Some(offset));
+    }
+    assert_eq!(resolver.offset(4, 0), None);
+    assert_eq!(resolver.offset(-1, 0), None);
+
+    assert_eq!(resolver.offset_span(&[2, 0, 2]), Some(3..5));
+    assert_eq!(resolver.offset_span(&[0, 1, 3, 2]), Some(1..9));
+    assert_eq!(resolver.offset_span(&[0, 1]), None);
+    assert_eq!(resolver.resolve_span(1..9), vec![0, 1, 3, 2]);
+}
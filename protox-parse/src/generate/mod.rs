@@ -1,3 +1,5 @@
+use std::collections::{hash_map, HashMap, HashSet};
+
 use logos::Span;
 use prost_types::{
     descriptor_proto, enum_descriptor_proto, field_descriptor_proto, source_code_info::Location,
@@ -8,7 +10,7 @@ use prost_types::{
     SourceCodeInfo, UninterpretedOption,
 };
 
-use self::lines::LineResolver;
+pub(crate) use self::lines::LineResolver;
 use crate::{
     ast, case::to_pascal_case, error::ParseErrorKind, index_to_i32, tag, MAX_MESSAGE_FIELD_NUMBER,
 };
@@ -53,6 +55,7 @@ struct Context {
     lines: LineResolver,
 }
 
+#[derive(Clone, Copy)]
 enum FieldScope {
     Message,
     Oneof,
@@ -82,6 +85,12 @@ impl Context {
                 import.comments,
             );
 
+            if import.value == name {
+                self.errors.push(ParseErrorKind::SelfImport {
+                    span: import.value_span.clone(),
+                });
+            }
+
             dependency.push(import.value);
             match import.kind {
                 Some((ast::ImportKind::Public, span)) => {
@@ -189,6 +198,29 @@ impl Context {
         }
     }
 
+    fn check_map_entry_names(&mut self, items: &[ast::MessageItem]) {
+        let mut entry_names: HashMap<String, Span> = HashMap::new();
+        for item in items {
+            if let ast::MessageItem::Field(field) = item {
+                if let ast::FieldKind::Map { .. } = field.kind {
+                    let name = to_pascal_case(&field.name.value) + "Entry";
+                    match entry_names.entry(name) {
+                        hash_map::Entry::Occupied(entry) => {
+                            self.errors.push(ParseErrorKind::MapEntryNameClash {
+                                name: entry.key().clone(),
+                                first: entry.get().clone(),
+                                second: field.name.span.clone(),
+                            })
+                        }
+                        hash_map::Entry::Vacant(entry) => {
+                            entry.insert(field.name.span.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     fn generate_message_body_descriptor(&mut self, ast: ast::MessageBody) -> DescriptorProto {
         let mut field = Vec::new();
         let mut extension = Vec::new();
@@ -207,6 +239,26 @@ impl Context {
             .count();
         oneof_decl.resize(real_oneof_count, OneofDescriptorProto::default());
 
+        self.check_map_entry_names(&ast.items);
+
+        let field_names: Vec<_> = ast
+            .items
+            .iter()
+            .flat_map(|item| match item {
+                ast::MessageItem::Field(field) => std::slice::from_ref(field),
+                ast::MessageItem::Oneof(oneof) => oneof.fields.as_slice(),
+                _ => &[],
+            })
+            .map(|field| {
+                let name = match field.kind {
+                    ast::FieldKind::Group { .. } => field.name.value.to_ascii_lowercase(),
+                    _ => field.name.value.clone(),
+                };
+                (name, field.name.span.clone())
+            })
+            .collect();
+        let mut reserved_name_spans = Vec::new();
+
         let mut real_oneof_index = 0;
         for item in ast.items {
             match item {
@@ -257,6 +309,7 @@ impl Context {
                 }
             }
         }
+        name_synthetic_oneofs(&field, &mut oneof_decl);
 
         let is_message_set = match ast
             .options
@@ -284,7 +337,8 @@ impl Context {
                     self.path.push(tag::message::RESERVED_NAME);
                     self.add_comments(reserved.span, reserved.comments);
                     for name in names {
-                        self.add_span_for(&[index_to_i32(reserved_name.len())], name.span);
+                        self.add_span_for(&[index_to_i32(reserved_name.len())], name.span.clone());
+                        reserved_name_spans.push(name.span);
                         reserved_name.push(name.value);
                     }
                     self.path.pop();
@@ -292,6 +346,16 @@ impl Context {
             }
         }
 
+        for (name, span) in field_names {
+            if let Some(index) = reserved_name.iter().position(|reserved| *reserved == name) {
+                self.errors.push(ParseErrorKind::ReservedNameUsed {
+                    name,
+                    reserved: reserved_name_spans[index].clone(),
+                    span,
+                });
+            }
+        }
+
         self.path.push(tag::message::EXTENSION_RANGE);
         for extensions in ast.extensions {
             self.add_comments(extensions.span.clone(), extensions.comments);
@@ -350,18 +414,10 @@ impl Context {
             && matches!(ast.label, Some((ast::FieldLabel::Optional, _)))
         {
             if let Some(oneof_tag) = oneof_tag {
-                let oneof_name = if ast.name.value.starts_with('_') {
-                    format!("X{}", &ast.name.value)
-                } else {
-                    format!("_{}", &ast.name.value)
-                };
-
+                // The oneof is named by `name_synthetic_oneofs` once all names in the message are known.
                 let oneof_index = index_to_i32(oneofs.len());
                 self.path.extend([oneof_tag, oneof_index]);
-                oneofs.push(OneofDescriptorProto {
-                    name: Some(oneof_name),
-                    options: None,
-                });
+                oneofs.push(OneofDescriptorProto::default());
                 self.pop_path(2);
 
                 (Some(true), Some(oneof_index))
@@ -486,11 +542,18 @@ impl Context {
                             span: ast.span.clone(),
                         });
                     }
-                    FieldScope::Message => {
-                        if let Some((_, span)) = ast.label {
+                    FieldScope::Message => match ast.label {
+                        Some((ast::FieldLabel::Required, span))
+                            if self.syntax == ast::Syntax::Proto3 =>
+                        {
+                            self.errors
+                                .push(ParseErrorKind::Proto3RequiredField { span });
+                        }
+                        Some((_, span)) => {
                             self.errors.push(ParseErrorKind::MapFieldWithLabel { span });
                         }
-                    }
+                        None => (),
+                    },
                 }
 
                 self.add_comments(ast.span, ast.comments);
@@ -546,6 +609,10 @@ impl Context {
         }
 
         let json_name = if let Some(o) = take_option(&mut ast.options, "json_name") {
+            if matches!(scope, FieldScope::Extend) {
+                self.errors
+                    .push(ParseErrorKind::InvalidExtendFieldJsonName { span: o.span() });
+            }
             self.add_span_for(&[tag::field::JSON_NAME], o.span());
             self.add_span_for(&[tag::field::JSON_NAME], o.value.span());
             self.generate_string_option_value(o.value)
@@ -779,6 +846,19 @@ impl Context {
         }
     }
 
+    fn generate_extension_range_number(&mut self, ast: ast::Int, max: i32) -> Option<i32> {
+        match ast.as_i32() {
+            Some(number) if 1 <= number && number <= max => Some(number),
+            _ => {
+                self.errors.push(ParseErrorKind::ExtensionRangeOutOfBounds {
+                    max,
+                    span: ast.span,
+                });
+                None
+            }
+        }
+    }
+
     fn generate_message_reserved_range(
         &mut self,
         range: ast::ReservedRange,
@@ -819,15 +899,16 @@ impl Context {
         let options = self.generate_options_list(options);
         self.path.pop();
 
-        let start = self.generate_message_number(range.start);
+        let max = if is_message_set {
+            i32::MAX - 1
+        } else {
+            MAX_MESSAGE_FIELD_NUMBER
+        };
+        let start = self.generate_extension_range_number(range.start, max);
         let end = match range.end {
             ast::ReservedRangeEnd::None => start,
-            ast::ReservedRangeEnd::Int(value) => self.generate_message_number(value),
-            ast::ReservedRangeEnd::Max(_) => Some(if is_message_set {
-                i32::MAX - 1
-            } else {
-                MAX_MESSAGE_FIELD_NUMBER
-            }),
+            ast::ReservedRangeEnd::Int(value) => self.generate_extension_range_number(value, max),
+            ast::ReservedRangeEnd::Max(_) => Some(max),
         };
 
         descriptor_proto::ExtensionRange {
@@ -931,6 +1012,14 @@ impl Context {
         let mut reserved_range = Vec::new();
         let mut reserved_name = Vec::new();
 
+        let value_spans: Vec<_> = ast
+            .values
+            .iter()
+            .map(|value| (value.name.span.clone(), value.number.span.clone()))
+            .collect();
+        let mut reserved_range_spans = Vec::new();
+        let mut reserved_name_spans = Vec::new();
+
         for value_ast in ast.values {
             self.path
                 .extend([tag::enum_::VALUE, index_to_i32(value.len())]);
@@ -945,6 +1034,7 @@ impl Context {
                     self.add_comments(reserved.span, reserved.comments);
                     for range in ranges {
                         self.path.push(index_to_i32(reserved_range.len()));
+                        reserved_range_spans.push(range.span());
                         reserved_range.push(self.generate_enum_reserved_range(range));
                         self.path.pop();
                     }
@@ -954,7 +1044,8 @@ impl Context {
                     self.path.push(tag::enum_::RESERVED_NAME);
                     self.add_comments(reserved.span, reserved.comments);
                     for name in names {
-                        self.add_span_for(&[index_to_i32(reserved_name.len())], name.span);
+                        self.add_span_for(&[index_to_i32(reserved_name.len())], name.span.clone());
+                        reserved_name_spans.push(name.span);
                         reserved_name.push(name.value);
                     }
                     self.path.pop();
@@ -962,6 +1053,15 @@ impl Context {
             }
         }
 
+        self.check_enum_reserved(
+            &value,
+            &value_spans,
+            &reserved_range,
+            &reserved_range_spans,
+            &reserved_name,
+            &reserved_name_spans,
+        );
+
         self.path.push(tag::enum_::OPTIONS);
         let options = self.generate_options(ast.options);
         self.path.pop();
@@ -978,6 +1078,46 @@ impl Context {
         }
     }
 
+    /// Checks that no value of an enum uses a reserved name or number. A value which uses both is reported twice.
+    fn check_enum_reserved(
+        &mut self,
+        values: &[EnumValueDescriptorProto],
+        value_spans: &[(Span, Span)],
+        reserved_ranges: &[enum_descriptor_proto::EnumReservedRange],
+        reserved_range_spans: &[Span],
+        reserved_names: &[String],
+        reserved_name_spans: &[Span],
+    ) {
+        for (value, (name_span, number_span)) in values.iter().zip(value_spans) {
+            if let Some(index) = reserved_names
+                .iter()
+                .position(|reserved| reserved == value.name())
+            {
+                self.errors.push(ParseErrorKind::ReservedNameUsed {
+                    name: value.name().to_owned(),
+                    reserved: reserved_name_spans[index].clone(),
+                    span: name_span.clone(),
+                });
+            }
+
+            let number = match value.number {
+                Some(number) => number,
+                None => continue,
+            };
+            if let Some(index) = reserved_ranges.iter().position(|range| {
+                matches!((range.start, range.end), (Some(start), Some(end)) if start <= number && number <= end)
+            }) {
+                let range = &reserved_ranges[index];
+                self.errors.push(ParseErrorKind::EnumReservedNumberUsed {
+                    number,
+                    range: format!("{} to {}", range.start(), range.end()),
+                    reserved: reserved_range_spans[index].clone(),
+                    span: number_span.clone(),
+                });
+            }
+        }
+    }
+
     fn generate_enum_value_descriptor(&mut self, ast: ast::EnumValue) -> EnumValueDescriptorProto {
         self.add_comments(ast.span, ast.comments);
         self.add_span_for(&[tag::enum_value::NAME], ast.name.span);
@@ -1264,6 +1404,34 @@ impl Context {
     }
 }
 
+/// Names the synthetic oneofs of proto3 optional fields in a message, using the same rules as protoc.
+///
+/// The name is the field name prefixed with an underscore, unless it already starts with one. If that conflicts
+/// with another field or oneof in the message, `X` is prepended until it is unique.
+fn name_synthetic_oneofs(fields: &[FieldDescriptorProto], oneofs: &mut [OneofDescriptorProto]) {
+    let mut names: HashSet<String> = fields
+        .iter()
+        .map(|field| field.name().to_owned())
+        .chain(oneofs.iter().filter_map(|oneof| oneof.name.clone()))
+        .collect();
+
+    for field in fields.iter().filter(|field| field.proto3_optional()) {
+        if let Some(index) = field.oneof_index {
+            let mut name = if field.name().starts_with('_') {
+                field.name().to_owned()
+            } else {
+                format!("_{}", field.name())
+            };
+            while names.contains(&name) {
+                name.insert(0, 'X');
+            }
+
+            names.insert(name.clone());
+            oneofs[index as usize].name = Some(name);
+        }
+    }
+}
+
 fn take_option(options: &mut Option<ast::OptionList>, name: &str) -> Option<ast::OptionBody> {
     if let Some(options) = options {
         if let Some(index) = options.options.iter().position(|o| o.has_name(name)) {
@@ -8,9 +8,9 @@
 use logos::Span;
 use prost_types::FileDescriptorProto;
 
-pub use self::error::ParseError;
+pub use self::{ast::Syntax, error::ParseError};
 
-mod ast;
+pub mod ast;
 mod case;
 mod error;
 mod generate;
@@ -20,7 +20,35 @@ mod tag;
 #[cfg(test)]
 mod tests;
 
-const MAX_MESSAGE_FIELD_NUMBER: i32 = 536_870_911;
+/// The largest field number allowed in a message.
+///
+/// # Examples
+///
+/// ```
+/// # use protox_parse::{parse, MAX_MESSAGE_FIELD_NUMBER};
+/// let source = format!("message Foo {{ optional int32 a = {}; }}", MAX_MESSAGE_FIELD_NUMBER);
+/// assert!(parse("foo.proto", &source).is_ok());
+///
+/// let source = format!("message Foo {{ optional int32 a = {}; }}", MAX_MESSAGE_FIELD_NUMBER + 1);
+/// assert!(parse("foo.proto", &source).is_err());
+/// ```
+pub const MAX_MESSAGE_FIELD_NUMBER: i32 = 536_870_911;
+
+/// The first field number in the range reserved for the protobuf implementation.
+///
+/// Fields may not use numbers from [`RESERVED_MESSAGE_FIELD_NUMBER_START`] to [`RESERVED_MESSAGE_FIELD_NUMBER_END`]
+/// inclusive.
+pub const RESERVED_MESSAGE_FIELD_NUMBER_START: i32 = 19_000;
+
+/// The last field number in the range reserved for the protobuf implementation.
+///
+/// See [`RESERVED_MESSAGE_FIELD_NUMBER_START`].
+pub const RESERVED_MESSAGE_FIELD_NUMBER_END: i32 = 19_999;
+
+/// The maximum depth to which messages and groups may be nested.
+///
+/// Files which nest messages more deeply than this are rejected, rather than risking a stack overflow.
+pub const MAX_MESSAGE_NESTING_DEPTH: u32 = 100;
 
 /// Parses a single protobuf source file into a [`FileDescriptorProto`].
 ///
@@ -74,6 +102,70 @@ const MAX_MESSAGE_FIELD_NUMBER: i32 = 536_870_911;
 /// })
 /// ```
 pub fn parse(name: &str, source: &str) -> Result<FileDescriptorProto, ParseError> {
+    parse_with_default_syntax(name, source, Some(Syntax::Proto2))
+}
+
+/// Parses a single protobuf source file into a [`FileDescriptorProto`], using the given syntax for files
+/// without a `syntax` statement.
+///
+/// If `default_syntax` is `None`, files without a `syntax` statement are rejected. Otherwise, this behaves
+/// the same as [`parse()`].
+///
+/// # Examples
+///
+/// ```
+/// # use protox_parse::{parse_with_default_syntax, Syntax};
+/// let file_descriptor = parse_with_default_syntax("foo.proto", "message Foo { int32 bar = 1; }", Some(Syntax::Proto3)).unwrap();
+/// assert_eq!(file_descriptor.syntax(), "proto3");
+///
+/// let err = parse_with_default_syntax("foo.proto", "message Foo {}", None).unwrap_err();
+/// assert_eq!(err.to_string(), "missing syntax statement");
+/// ```
+pub fn parse_with_default_syntax(
+    name: &str,
+    source: &str,
+    default_syntax: Option<Syntax>,
+) -> Result<FileDescriptorProto, ParseError> {
+    let mut ast = parse_ast(name, source)?;
+
+    if ast.syntax_span.is_none() {
+        match default_syntax {
+            Some(syntax) => ast.syntax = syntax,
+            None => {
+                return Err(ParseError::new(
+                    vec![error::ParseErrorKind::MissingSyntax { span: 0..0 }],
+                    name,
+                    source.to_owned(),
+                ))
+            }
+        }
+    }
+
+    generate::generate_file(ast, name, source)
+        .map_err(|errors| ParseError::new(errors, name, source.to_owned()))
+}
+
+/// Parses a single protobuf source file into its syntax tree, without converting it to a [`FileDescriptorProto`].
+///
+/// This is useful for tools such as formatters and linters, which need the structure of the source file rather
+/// than its descriptor. Only syntax errors are reported: errors which [`parse()`] finds while generating the
+/// descriptor, such as invalid field numbers or option values, are not.
+///
+/// # Examples
+///
+/// ```
+/// # use protox_parse::{ast, parse_ast};
+/// let file = parse_ast("foo.proto", "package foo; message Bar { optional int32 baz = 1; }").unwrap();
+/// assert_eq!(file.package.unwrap().name.to_string(), "foo");
+/// match &file.items[0] {
+///     ast::FileItem::Message(message) => assert_eq!(message.name.value, "Bar"),
+///     _ => panic!("expected a message"),
+/// }
+///
+/// let err = parse_ast("foo.proto", "message {}").unwrap_err();
+/// assert_eq!(err.to_string(), "expected an identifier, but found '{'");
+/// ```
+pub fn parse_ast(name: &str, source: &str) -> Result<ast::File, ParseError> {
     if source.len() > MAX_FILE_LEN {
         return Err(ParseError::new(
             vec![error::ParseErrorKind::FileTooLarge],
@@ -82,11 +174,26 @@ pub fn parse(name: &str, source: &str) -> Result<FileDescriptorProto, ParseError
         ));
     }
 
-    let ast = parse::parse_file(source)
-        .map_err(|errors| ParseError::new(errors, name, source.to_owned()))?;
+    parse::parse_file(source).map_err(|errors| ParseError::new(errors, name, source.to_owned()))
+}
 
-    generate::generate_file(ast, name, source)
-        .map_err(|errors| ParseError::new(errors, name, source.to_owned()))
+/// Converts the `span` of a [`Location`](prost_types::source_code_info::Location) in the source code info generated
+/// by [`parse()`] back into a range of byte offsets in `source`.
+///
+/// Returns `None` if the span is not a valid location span, or does not fit within `source`'s lines.
+///
+/// # Examples
+///
+/// ```
+/// # use protox_parse::{parse, location_span};
+/// let source = "syntax = 'proto3';\nmessage Foo {}";
+/// let file = parse("foo.proto", source).unwrap();
+/// let location = file.source_code_info.unwrap().location.into_iter().find(|l| l.path == [4, 0, 1]).unwrap();
+/// assert_eq!(location_span(source, &location.span), Some(27..30));
+/// assert_eq!(&source[27..30], "Foo");
+/// ```
+pub fn location_span(source: &str, span: &[i32]) -> Option<std::ops::Range<usize>> {
+    generate::LineResolver::new(source).offset_span(span)
 }
 
 const MAX_FILE_LEN: usize = i32::MAX as usize;
@@ -13,6 +13,7 @@ use crate::{
     error::ParseErrorKind,
     join_span,
     lex::{EqFloat, Token},
+    MAX_MESSAGE_NESTING_DEPTH,
 };
 
 mod comments;
@@ -31,6 +32,7 @@ struct Parser<'a> {
     lexer: Lexer<'a, Token<'a>>,
     peek: Option<Result<(Token<'a>, Span), ()>>,
     comments: Comments,
+    depth: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -56,6 +58,7 @@ impl<'a> Parser<'a> {
             lexer: Token::lexer(source),
             comments: Comments::new(),
             peek: None,
+            depth: 0,
         }
     }
 
@@ -71,6 +74,12 @@ impl<'a> Parser<'a> {
                 syntax = parsed_syntax;
                 syntax_span = Some((span, comments));
             }
+            Some((Token::EDITION, span)) => {
+                // Editions are rejected, since the descriptor types have no `FeatureSet` to resolve features such
+                // as `field_presence` into.
+                self.add_error(ParseErrorKind::EditionsUnsupported { span });
+                return Err(());
+            }
             Some((_, span)) => {
                 file_span = span;
             }
@@ -250,7 +259,7 @@ impl<'a> Parser<'a> {
         self.expect_eq(Token::LeftBrace)?;
         let comments = self.parse_trailing_comment(leading_comments);
 
-        let (body, end) = self.parse_message_body()?;
+        let (body, end) = self.parse_message_body(&name)?;
 
         Ok(ast::Message {
             name,
@@ -260,7 +269,22 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_message_body(&mut self) -> Result<(ast::MessageBody, Span), ()> {
+    fn parse_message_body(&mut self, name: &ast::Ident) -> Result<(ast::MessageBody, Span), ()> {
+        // Guard against stack overflows when parsing deeply nested messages and groups.
+        if self.depth >= MAX_MESSAGE_NESTING_DEPTH {
+            self.add_error(ParseErrorKind::NestingTooDeep {
+                span: name.span.clone(),
+            });
+            return Err(());
+        }
+
+        self.depth += 1;
+        let result = self.parse_message_items();
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_message_items(&mut self) -> Result<(ast::MessageBody, Span), ()> {
         let mut items = Vec::new();
         let mut options = Vec::new();
         let mut reserved = Vec::new();
@@ -340,10 +364,10 @@ impl<'a> Parser<'a> {
         let ty_start = self.expect_eq(Token::MAP)?;
 
         self.expect_eq(Token::LeftAngleBracket)?;
-        let (key_ty, key_ty_span) = self.parse_field_type(&[ExpectedToken::COMMA])?;
+        let (key_ty, key_ty_span) = self.parse_map_field_type(&[ExpectedToken::COMMA])?;
         self.expect_eq(Token::Comma)?;
         let (value_ty, value_ty_span) =
-            self.parse_field_type(&[ExpectedToken::RIGHT_ANGLE_BRACKET])?;
+            self.parse_map_field_type(&[ExpectedToken::RIGHT_ANGLE_BRACKET])?;
         let ty_end = self.expect_eq(Token::RightAngleBracket)?;
 
         let name = self.parse_ident()?;
@@ -407,7 +431,7 @@ impl<'a> Parser<'a> {
 
         let comments = self.parse_trailing_comment(leading_comments);
 
-        let (body, end) = self.parse_message_body()?;
+        let (body, end) = self.parse_message_body(&name)?;
 
         Ok(ast::Field {
             label,
@@ -430,6 +454,21 @@ impl<'a> Parser<'a> {
 
         let name = self.parse_ident()?;
 
+        if let Some((Token::LeftBrace, _)) = self.peek_skip_comments()? {
+            // A field type of `service` followed by a body looks like a nested service definition.
+            let is_service = matches!(
+                &ty,
+                ast::Ty::Named(ast::TypeName { leading_dot: None, name })
+                    if name.parts.len() == 1 && name.parts[0].value == "service"
+            );
+            if label.is_none() && is_service {
+                self.add_error(ParseErrorKind::ServiceNotAtFileScope {
+                    span: join_span(ty_span, name.span),
+                });
+                return Err(());
+            }
+        }
+
         self.expect_eq(Token::Equals)?;
 
         let number = self.parse_int()?;
@@ -699,6 +738,31 @@ impl<'a> Parser<'a> {
         })
     }
 
+    fn parse_map_field_type(
+        &mut self,
+        terminators: &[ExpectedToken],
+    ) -> Result<(ast::Ty, Span), ()> {
+        if let Some((Token::GROUP, _)) = self.peek()? {
+            // `group` may be an ordinary type name, but if it is followed by another identifier this
+            // looks like an attempt to define a group as the key or value type.
+            let group = self.parse_ident()?;
+            if let Some((Token::Ident(_), _)) = self.peek_skip_comments()? {
+                self.add_error(ParseErrorKind::InvalidMapFieldGroupType { span: group.span });
+                return Err(());
+            }
+
+            let name = self.parse_full_ident_rest(group, terminators)?;
+            let type_name = ast::TypeName {
+                name,
+                leading_dot: None,
+            };
+            let span = type_name.span();
+            return Ok((ast::Ty::Named(type_name), span));
+        }
+
+        self.parse_field_type(terminators)
+    }
+
     fn parse_field_type(&mut self, terminators: &[ExpectedToken]) -> Result<(ast::Ty, Span), ()> {
         let scalar_ty = match self.peek()? {
             Some((Token::DOUBLE, span)) => (ast::Ty::Double, span),
@@ -743,7 +807,7 @@ impl<'a> Parser<'a> {
                     span: join_span(start, end),
                 })
             }
-            Some((Token::StringLiteral(_), _)) => {
+            Some((Token::StringLiteral(_) | Token::Ident(_), _)) => {
                 let (names, end) = self.parse_reserved_names()?;
                 let comments = self.parse_trailing_comment(leading_comments);
                 Ok(ast::Reserved {
@@ -782,13 +846,13 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_reserved_names(&mut self) -> Result<(Vec<ast::Ident>, Span), ()> {
-        let mut names = vec![self.parse_ident_string()?];
+        let mut names = vec![self.parse_reserved_name()?];
 
         let end = loop {
             match self.peek_skip_comments()? {
                 Some((Token::Comma, _)) => {
                     self.bump();
-                    names.push(self.parse_ident_string()?);
+                    names.push(self.parse_reserved_name()?);
                 }
                 Some((Token::Semicolon, _)) => break self.bump(),
                 _ => self.unexpected_token("',' or ';'")?,
@@ -798,6 +862,21 @@ impl<'a> Parser<'a> {
         Ok((names, end))
     }
 
+    fn parse_reserved_name(&mut self) -> Result<ast::Ident, ()> {
+        match self.peek_skip_comments()? {
+            Some((Token::Ident(_), _)) => {
+                // Identifiers are only valid here in editions, but parse them anyway to give a clearer error.
+                let name = self.parse_ident()?;
+                self.add_error(ParseErrorKind::ReservedNameIdentifier {
+                    name: name.value.clone(),
+                    span: name.span.clone(),
+                });
+                Ok(name)
+            }
+            _ => self.parse_ident_string(),
+        }
+    }
+
     fn parse_ident_string(&mut self) -> Result<ast::Ident, ()> {
         let (value, span) = self.parse_utf8_string()?;
         if !is_valid_ident(&value) {
@@ -1021,7 +1100,16 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_full_ident(&mut self, terminators: &[ExpectedToken]) -> Result<ast::FullIdent, ()> {
-        let mut result = vec![self.parse_ident()?];
+        let first = self.parse_ident()?;
+        self.parse_full_ident_rest(first, terminators)
+    }
+
+    fn parse_full_ident_rest(
+        &mut self,
+        first: ast::Ident,
+        terminators: &[ExpectedToken],
+    ) -> Result<ast::FullIdent, ()> {
+        let mut result = vec![first];
 
         loop {
             match self.peek_skip_comments()? {
@@ -1,5 +1,5 @@
 use insta::assert_debug_snapshot;
-use prost_types::FileDescriptorProto;
+use prost_types::{field_descriptor_proto::Label, FileDescriptorProto};
 
 use crate::error::ParseErrorKind::{self, *};
 
@@ -91,6 +91,14 @@ fn map_field_invalid_type() {
         ),
         Err(vec![InvalidMapFieldKeyType { span: 34..40 }]),
     );
+    assert_eq!(
+        parse(
+            r#"message Message {
+            map<bytes, string> field = 1;
+        }"#
+        ),
+        Err(vec![InvalidMapFieldKeyType { span: 34..39 }]),
+    );
     assert_eq!(
         parse(
             r#"message Message {
@@ -120,6 +128,33 @@ fn map_field_invalid_type() {
     ));
 }
 
+#[test]
+fn map_field_group_type() {
+    assert_eq!(
+        parse(
+            r#"message Message {
+            map<string, group Foo { optional int32 a = 1; }> field = 1;
+        }"#
+        ),
+        Err(vec![InvalidMapFieldGroupType { span: 42..47 }]),
+    );
+    assert_eq!(
+        parse(
+            r#"message Message {
+            map<group Foo {}, string> field = 1;
+        }"#
+        ),
+        Err(vec![InvalidMapFieldGroupType { span: 34..39 }]),
+    );
+    assert!(parse(
+        r#"message Message {
+            map<string, group> field = 1;
+            map<string, group.Foo> field2 = 2;
+        }"#
+    )
+    .is_ok());
+}
+
 #[test]
 fn invalid_message_number() {
     assert_eq!(
@@ -153,6 +188,19 @@ fn proto3_default_value() {
         ),
         Err(vec![Proto3DefaultValue { span: 103..115 }]),
     );
+    assert_eq!(
+        parse(
+            r#"syntax = 'proto3';
+            message Message {
+                int32 foo = 1 [default = 5];
+                string bar = 2 [deprecated = true, default = "bar"];
+            }"#
+        ),
+        Err(vec![
+            Proto3DefaultValue { span: 80..91 },
+            Proto3DefaultValue { span: 145..160 },
+        ]),
+    );
 }
 
 #[test]
@@ -171,13 +219,31 @@ fn map_field_with_label() {
             syntax = 'proto3';
 
             message Message {
-                required map<int32, string> field = 1;
+                repeated map<int32, string> field = 1;
             }"#
         ),
         Err(vec![MapFieldWithLabel { span: 79..87 }]),
     );
 }
 
+#[test]
+fn proto2_map_field_without_label() {
+    let file = parse(
+        r#"
+        syntax = 'proto2';
+
+        message Message {
+            map<int32, string> field = 1;
+        }"#,
+    )
+    .unwrap();
+
+    let field = &file.message_type[0].field[0];
+    assert_eq!(field.name(), "field");
+    assert_eq!(field.label(), Label::Repeated);
+    assert_eq!(field.type_name(), "FieldEntry");
+}
+
 #[test]
 fn message_reserved_range_extrema() {
     assert_eq!(
@@ -203,6 +269,197 @@ fn message_reserved_range_extrema() {
     ));
 }
 
+#[test]
+fn message_extension_range_extrema() {
+    assert_eq!(
+        parse(
+            r#"message Message {
+                extensions 0 to 1;
+            }"#
+        ),
+        Err(vec![ExtensionRangeOutOfBounds {
+            max: 536870911,
+            span: 45..46
+        }]),
+    );
+    assert_eq!(
+        parse(
+            r#"message Message {
+                extensions 100 to 999999999;
+            }"#
+        ),
+        Err(vec![ExtensionRangeOutOfBounds {
+            max: 536870911,
+            span: 52..61
+        }]),
+    );
+    assert_eq!(
+        parse(
+            r#"message Message {
+                option message_set_wire_format = true;
+                extensions 4 to 2147483647;
+            }"#
+        ),
+        Err(vec![ExtensionRangeOutOfBounds {
+            max: 2147483646,
+            span: 105..115
+        }]),
+    );
+
+    let file = parse(
+        r#"message Message {
+            extensions 100 to max;
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(file.message_type[0].extension_range[0].start(), 100);
+    assert_eq!(file.message_type[0].extension_range[0].end(), 536870912);
+
+    let file = parse(
+        r#"message Message {
+            option message_set_wire_format = true;
+            extensions 4 to 2147483646;
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(file.message_type[0].extension_range[0].end(), i32::MAX);
+}
+
+#[test]
+fn service_not_at_file_scope() {
+    assert_eq!(
+        parse(
+            r#"message Message {
+                service Service {
+                    rpc Method(Message) returns (Message);
+                }
+            }"#
+        ),
+        Err(vec![ServiceNotAtFileScope { span: 34..49 }]),
+    );
+    assert!(parse(
+        r#"message Message {
+            optional service service = 1;
+        }
+
+        message service {}"#
+    )
+    .is_ok());
+}
+
+#[test]
+fn enum_reserved_name_used() {
+    assert_eq!(
+        parse("enum Foo { reserved 'ZERO', 'ONE'; ZERO = 0; ONE = 1; TWO = 2; }"),
+        Err(vec![
+            ReservedNameUsed {
+                name: "ZERO".to_owned(),
+                reserved: 20..26,
+                span: 35..39,
+            },
+            ReservedNameUsed {
+                name: "ONE".to_owned(),
+                reserved: 28..33,
+                span: 45..48,
+            },
+        ]),
+    );
+}
+
+#[test]
+fn enum_reserved_number_used() {
+    assert_eq!(
+        parse("enum Foo { reserved 1, 3 to max; reserved 'TWO'; ZERO = 0; ONE = 1; TWO = 3; }"),
+        Err(vec![
+            EnumReservedNumberUsed {
+                number: 1,
+                range: "1 to 1".to_owned(),
+                reserved: 20..21,
+                span: 65..66,
+            },
+            ReservedNameUsed {
+                name: "TWO".to_owned(),
+                reserved: 42..47,
+                span: 68..71,
+            },
+            EnumReservedNumberUsed {
+                number: 3,
+                range: "3 to 2147483647".to_owned(),
+                reserved: 23..31,
+                span: 74..75,
+            },
+        ]),
+    );
+}
+
+#[test]
+fn message_reserved_name_used() {
+    assert_eq!(
+        parse(
+            "message Foo {
+                reserved 'foo', 'bar', 'baz';
+                optional int32 foo = 1;
+                oneof o { int32 bar = 2; }
+                optional group Baz = 3 {}
+                optional int32 qux = 4;
+            }"
+        ),
+        Err(vec![
+            ReservedNameUsed {
+                name: "foo".to_owned(),
+                reserved: 39..44,
+                span: 91..94,
+            },
+            ReservedNameUsed {
+                name: "bar".to_owned(),
+                reserved: 46..51,
+                span: 132..135,
+            },
+            ReservedNameUsed {
+                name: "baz".to_owned(),
+                reserved: 53..58,
+                span: 174..177,
+            },
+        ]),
+    );
+}
+
+#[test]
+fn reserved_name_identifier() {
+    assert_eq!(
+        parse("message Foo { reserved foo, 'bar'; }"),
+        Err(vec![ReservedNameIdentifier {
+            name: "foo".to_owned(),
+            span: 23..26,
+        }]),
+    );
+    assert_eq!(
+        parse("enum Foo { ZERO = 0; reserved ONE, TWO; }"),
+        Err(vec![
+            ReservedNameIdentifier {
+                name: "ONE".to_owned(),
+                span: 30..33,
+            },
+            ReservedNameIdentifier {
+                name: "TWO".to_owned(),
+                span: 35..38,
+            },
+        ]),
+    );
+}
+
+#[test]
+fn self_import() {
+    assert_eq!(
+        parse("import 'dep.proto'; import 'test.proto';"),
+        Err(vec![SelfImport { span: 27..39 }]),
+    );
+    assert_eq!(
+        parse("import public 'test.proto';"),
+        Err(vec![SelfImport { span: 14..26 }]),
+    );
+}
+
 #[test]
 fn extend_required_field() {
     assert_eq!(
@@ -272,6 +529,45 @@ fn proto3_required_field() {
         ),
         Err(vec![Proto3RequiredField { span: 79..87 }]),
     );
+    assert_eq!(
+        parse(
+            r#"
+            syntax = 'proto3';
+
+            message Message {
+                required map<int32, string> foo = 1;
+            }
+            "#
+        ),
+        Err(vec![Proto3RequiredField { span: 79..87 }]),
+    );
+    assert_eq!(
+        parse(
+            r#"
+            syntax = 'proto3';
+
+            message Message {
+                required group Foo = 1 {}
+            }
+            "#
+        ),
+        Err(vec![
+            Proto3RequiredField { span: 79..87 },
+            Proto3GroupField { span: 79..104 },
+        ]),
+    );
+    assert_eq!(
+        parse(
+            r#"
+            syntax = 'proto3';
+
+            extend Foo {
+                required int32 foo = 1;
+            }
+            "#
+        ),
+        Err(vec![RequiredExtendField { span: 74..82 }]),
+    );
 }
 
 #[test]
@@ -425,6 +721,22 @@ fn message_field_json_name() {
     ));
 }
 
+#[test]
+fn extend_field_json_name() {
+    assert_eq!(
+        parse(
+            r#"message Message {
+            extensions 1;
+        }
+
+        extend Message {
+            optional int32 field = 1 [json_name = "foo"];
+        }"#
+        ),
+        Err(vec![InvalidExtendFieldJsonName { span: 118..135 }]),
+    );
+}
+
 #[test]
 fn field_default_value() {
     assert_debug_snapshot!(parse(
@@ -708,6 +1020,32 @@ fn field_default_invalid_type() {
             span: 81..84,
         }]),
     );
+    assert_eq!(
+        parse(
+            r#"
+            message Message {
+                optional bool foo = 1 [default = 999];
+            }"#
+        ),
+        Err(vec![ValueInvalidType {
+            expected: "either 'true' or 'false'".to_owned(),
+            actual: "999".to_owned(),
+            span: 80..83,
+        }]),
+    );
+    assert_eq!(
+        parse(
+            r#"
+            message Message {
+                optional bytes foo = 1 [default = 5];
+            }"#
+        ),
+        Err(vec![ValueInvalidType {
+            expected: "a string".to_owned(),
+            actual: "5".to_owned(),
+            span: 81..82,
+        }]),
+    );
 }
 
 #[test]
@@ -767,3 +1105,157 @@ fn options() {
         }]),
     );
 }
+
+#[test]
+fn service_method_streaming() {
+    let cases = [
+        ("rpc Method(Foo) returns (Foo);", None, None),
+        ("rpc Method(stream Foo) returns (Foo);", Some(true), None),
+        ("rpc Method(Foo) returns (stream Foo);", None, Some(true)),
+        (
+            "rpc Method(stream Foo) returns (stream Foo);",
+            Some(true),
+            Some(true),
+        ),
+    ];
+
+    for (method, client_streaming, server_streaming) in cases {
+        let file = parse(&format!("service Service {{ {} }}", method)).unwrap();
+        let method = &file.service[0].method[0];
+        assert_eq!(
+            method.client_streaming,
+            client_streaming,
+            "{}",
+            method.name()
+        );
+        assert_eq!(
+            method.server_streaming,
+            server_streaming,
+            "{}",
+            method.name()
+        );
+    }
+}
+
+#[test]
+fn default_syntax() {
+    use crate::{parse_with_default_syntax, Syntax};
+
+    assert_eq!(
+        parse_with_default_syntax("test.proto", "message Foo {}", None)
+            .map_err(|err| err.into_inner()),
+        Err(vec![MissingSyntax { span: 0..0 }]),
+    );
+    assert!(
+        parse_with_default_syntax("test.proto", "syntax = 'proto2'; message Foo {}", None).is_ok()
+    );
+
+    let file = parse_with_default_syntax(
+        "test.proto",
+        "message Foo { int32 a = 1; }",
+        Some(Syntax::Proto3),
+    )
+    .unwrap();
+    assert_eq!(file.syntax(), "proto3");
+    assert_eq!(file.message_type[0].field[0].label(), Label::Optional);
+}
+
+#[test]
+fn editions_unsupported() {
+    assert_eq!(
+        parse(
+            "edition = '2023'; message Foo { int32 a = 1 [features.field_presence = EXPLICIT]; }"
+        ),
+        Err(vec![EditionsUnsupported { span: 0..7 }]),
+    );
+}
+
+#[test]
+fn keyword_field_names() {
+    let file = parse(
+        "syntax = 'proto3';
+        message Foo {
+            int32 message = 1;
+            int32 enum = 2;
+            int32 service = 3;
+            int32 optional = 4;
+            optional int32 required = 5;
+            repeated int32 repeated = 6;
+            map<int32, int32> map = 7;
+            int32 syntax = 8;
+        }",
+    )
+    .unwrap();
+
+    let fields = &file.message_type[0].field;
+    assert_eq!(
+        fields.iter().map(|field| field.name()).collect::<Vec<_>>(),
+        ["message", "enum", "service", "optional", "required", "repeated", "map", "syntax"]
+    );
+    assert_eq!(fields[3].proto3_optional, None);
+    assert_eq!(fields[4].proto3_optional, Some(true));
+    assert_eq!(fields[5].label(), Label::Repeated);
+}
+
+#[test]
+fn map_entry_name_clash() {
+    assert_eq!(
+        parse(
+            "message Foo {
+                map<string, int32> foo_bar = 1;
+                map<string, int32> fooBar = 2;
+                map<string, int32> baz = 3;
+            }"
+        ),
+        Err(vec![MapEntryNameClash {
+            name: "FooBarEntry".to_owned(),
+            first: 49..56,
+            second: 97..103,
+        }]),
+    );
+}
+
+#[test]
+fn message_nesting_too_deep() {
+    let nested = |depth: u32| {
+        let mut source: String = (0..depth).map(|i| format!("message M{} {{ ", i)).collect();
+        source.push_str(&"}".repeat(depth as usize));
+        source
+    };
+
+    assert!(parse(&nested(crate::MAX_MESSAGE_NESTING_DEPTH)).is_ok());
+
+    let source = nested(crate::MAX_MESSAGE_NESTING_DEPTH + 1);
+    let start = source.find("M100").unwrap();
+    assert_eq!(
+        parse(&source),
+        Err(vec![NestingTooDeep {
+            span: start..start + 4
+        }]),
+    );
+}
+
+#[test]
+fn parse_ast_skips_generation() {
+    let source = "message Foo { optional int32 bar = 0; }";
+
+    assert_eq!(
+        parse(source),
+        Err(vec![InvalidMessageNumber { span: 35..36 }])
+    );
+
+    let file = crate::parse_ast("test.proto", source).unwrap();
+    match &file.items[..] {
+        [crate::ast::FileItem::Message(message)] => {
+            assert_eq!(message.name.value, "Foo");
+            match &message.body.items[..] {
+                [crate::ast::MessageItem::Field(field)] => {
+                    assert_eq!(field.name.value, "bar");
+                    assert_eq!(field.number.as_i32(), Some(0));
+                }
+                items => panic!("unexpected items: {:?}", items),
+            }
+        }
+        items => panic!("unexpected items: {:?}", items),
+    }
+}
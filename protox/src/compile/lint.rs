@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+
+use miette::{NamedSource, SourceSpan};
+use prost_reflect::{
+    Cardinality, DynamicMessage, EnumDescriptor, FieldDescriptor, FileDescriptor, Kind,
+    MessageDescriptor, Value,
+};
+use prost_types::{FieldDescriptorProto, FileDescriptorProto};
+
+use crate::warning::{LintLevel, Warning, WarningKind};
+
+/// The levels of optional lints.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct LintOptions {
+    pub jstype: LintLevel,
+    pub recursive_required_fields: LintLevel,
+    pub unused_imports: LintLevel,
+}
+
+/// Runs all lints over a file which has been added to the descriptor pool.
+///
+/// The last `hidden_dependencies` dependencies of the file were added by the compiler, and are not checked.
+pub(crate) fn lint_file(
+    file: &FileDescriptor,
+    source: Option<&str>,
+    hidden_dependencies: usize,
+    options: LintOptions,
+) -> Vec<Warning> {
+    let mut lints = Lints {
+        file,
+        source,
+        imports: file.file_descriptor_proto().dependency.len() - hidden_dependencies,
+        options,
+        warnings: Vec::new(),
+    };
+
+    for message in file.messages() {
+        lints.lint_message(&message);
+    }
+    for extension in file.extensions() {
+        lints.lint_jstype(
+            extension.full_name(),
+            extension.kind(),
+            extension.path(),
+            extension.field_descriptor_proto(),
+        );
+    }
+    lints.lint_unused_imports();
+
+    lints.warnings
+}
+
+struct Lints<'a> {
+    file: &'a FileDescriptor,
+    source: Option<&'a str>,
+    imports: usize,
+    options: LintOptions,
+    warnings: Vec<Warning>,
+}
+
+impl<'a> Lints<'a> {
+    fn lint_message(&mut self, message: &MessageDescriptor) {
+        if !message.is_map_entry() {
+            for field in message.fields() {
+                self.lint_jstype(
+                    field.full_name(),
+                    field.kind(),
+                    field.path(),
+                    field.field_descriptor_proto(),
+                );
+            }
+        }
+        for extension in message.child_extensions() {
+            self.lint_jstype(
+                extension.full_name(),
+                extension.kind(),
+                extension.path(),
+                extension.field_descriptor_proto(),
+            );
+        }
+
+        self.lint_recursive_required_fields(message);
+
+        for nested in message.child_messages() {
+            self.lint_message(&nested);
+        }
+    }
+
+    fn lint_recursive_required_fields(&mut self, message: &MessageDescriptor) {
+        if self.options.recursive_required_fields == LintLevel::Allow {
+            return;
+        }
+
+        for field in message.fields() {
+            if let Some(field_message) = required_message_type(&field) {
+                if requires_message(&field_message, message, &mut HashSet::new()) {
+                    self.warnings
+                        .push(Warning::from_kind(WarningKind::RecursiveRequiredField {
+                            name: self.file.name().to_owned(),
+                            field: field.full_name().to_owned(),
+                            span: self.span(field.path()),
+                            source_code: self.named_source(),
+                        }));
+                }
+            }
+        }
+    }
+
+    fn lint_jstype(
+        &mut self,
+        full_name: &str,
+        kind: Kind,
+        path: &[i32],
+        field: &FieldDescriptorProto,
+    ) {
+        if self.options.jstype == LintLevel::Allow
+            || self.file.file_descriptor_proto().syntax() != "proto3"
+            || field.options.as_ref().is_some_and(|o| o.jstype.is_some())
+        {
+            return;
+        }
+
+        if matches!(
+            kind,
+            Kind::Int64 | Kind::Uint64 | Kind::Sint64 | Kind::Fixed64 | Kind::Sfixed64
+        ) {
+            self.warnings
+                .push(Warning::from_kind(WarningKind::Int64WithoutJsType {
+                    name: self.file.name().to_owned(),
+                    field: full_name.to_owned(),
+                    span: self.span(path),
+                    source_code: self.named_source(),
+                }));
+        }
+    }
+
+    fn lint_unused_imports(&mut self) {
+        /// The field number of `dependency` in `FileDescriptorProto`.
+        const DEPENDENCY: i32 = 3;
+
+        if self.options.unused_imports == LintLevel::Allow {
+            return;
+        }
+
+        let mut used = HashSet::new();
+        collect_used_files(self.file, &mut used);
+
+        let public_dependencies = &self.file.file_descriptor_proto().public_dependency;
+        for (index, dependency) in self.file.dependencies().enumerate().take(self.imports) {
+            // Public imports are re-exported to importing files, so they may be used even if this file does not
+            // reference them.
+            if public_dependencies.contains(&(index as i32)) {
+                continue;
+            }
+
+            if !provides_used_file(&dependency, &used) {
+                self.warnings
+                    .push(Warning::from_kind(WarningKind::UnusedImport {
+                        name: self.file.name().to_owned(),
+                        import: dependency.name().to_owned(),
+                        span: self.span(&[DEPENDENCY, index as i32]),
+                        source_code: self.named_source(),
+                    }));
+            }
+        }
+    }
+
+    fn named_source(&self) -> Option<NamedSource<String>> {
+        self.source
+            .map(|source| NamedSource::new(self.file.name(), source.to_owned()))
+    }
+
+    fn span(&self, path: &[i32]) -> Option<SourceSpan> {
+        source_span(self.file.file_descriptor_proto(), self.source?, path)
+    }
+}
+
+/// Gets the span of the definition at `path` in the source of a file, using its source code info.
+pub(crate) fn source_span(
+    file: &FileDescriptorProto,
+    source: &str,
+    path: &[i32],
+) -> Option<SourceSpan> {
+    let location = file
+        .source_code_info
+        .as_ref()?
+        .location
+        .iter()
+        .find(|location| location.path == path)?;
+
+    protox_parse::location_span(source, &location.span).map(SourceSpan::from)
+}
+
+/// Returns the message type of a field if it is a required message field.
+fn required_message_type(field: &FieldDescriptor) -> Option<MessageDescriptor> {
+    match (field.cardinality(), field.kind()) {
+        (Cardinality::Required, Kind::Message(message)) => Some(message),
+        _ => None,
+    }
+}
+
+/// Returns true if `message` transitively contains a required field of type `target`.
+fn requires_message(
+    message: &MessageDescriptor,
+    target: &MessageDescriptor,
+    visited: &mut HashSet<String>,
+) -> bool {
+    if message == target {
+        return true;
+    }
+    if !visited.insert(message.full_name().to_owned()) {
+        return false;
+    }
+
+    message.fields().any(|field| {
+        required_message_type(&field)
+            .is_some_and(|field_message| requires_message(&field_message, target, visited))
+    })
+}
+
+/// Collects the names of all files which define a type, extension or option referenced by `file`.
+fn collect_used_files(file: &FileDescriptor, used: &mut HashSet<String>) {
+    fn add_kind(kind: Kind, used: &mut HashSet<String>) {
+        match kind {
+            Kind::Message(message) => add_message(&message, used),
+            Kind::Enum(enum_) => add_enum(&enum_, used),
+            _ => (),
+        }
+    }
+
+    fn add_message(message: &MessageDescriptor, used: &mut HashSet<String>) {
+        used.insert(message.parent_file().name().to_owned());
+    }
+
+    fn add_enum(enum_: &EnumDescriptor, used: &mut HashSet<String>) {
+        used.insert(enum_.parent_file().name().to_owned());
+    }
+
+    fn add_options(options: &DynamicMessage, used: &mut HashSet<String>) {
+        for (extension, value) in options.extensions() {
+            used.insert(extension.parent_file().name().to_owned());
+            add_value(value, used);
+        }
+        for (_, value) in options.fields() {
+            add_value(value, used);
+        }
+    }
+
+    fn add_value(value: &Value, used: &mut HashSet<String>) {
+        match value {
+            Value::Message(message) => add_options(message, used),
+            Value::List(values) => values.iter().for_each(|value| add_value(value, used)),
+            _ => (),
+        }
+    }
+
+    fn visit_field(field: &FieldDescriptor, used: &mut HashSet<String>) {
+        add_kind(field.kind(), used);
+        add_options(&field.options(), used);
+    }
+
+    fn visit_enum(enum_: &EnumDescriptor, used: &mut HashSet<String>) {
+        add_options(&enum_.options(), used);
+        for value in enum_.values() {
+            add_options(&value.options(), used);
+        }
+    }
+
+    fn visit_message(message: &MessageDescriptor, used: &mut HashSet<String>) {
+        add_options(&message.options(), used);
+        for field in message.fields() {
+            visit_field(&field, used);
+        }
+        for oneof in message.oneofs() {
+            add_options(&oneof.options(), used);
+        }
+        for extension in message.child_extensions() {
+            add_message(&extension.containing_message(), used);
+            add_kind(extension.kind(), used);
+            add_options(&extension.options(), used);
+        }
+        for nested in message.child_messages() {
+            visit_message(&nested, used);
+        }
+        for enum_ in message.child_enums() {
+            visit_enum(&enum_, used);
+        }
+    }
+
+    add_options(&file.options(), used);
+    for message in file.messages() {
+        visit_message(&message, used);
+    }
+    for enum_ in file.enums() {
+        visit_enum(&enum_, used);
+    }
+    for extension in file.extensions() {
+        add_message(&extension.containing_message(), used);
+        add_kind(extension.kind(), used);
+        add_options(&extension.options(), used);
+    }
+    for service in file.services() {
+        add_options(&service.options(), used);
+        for method in service.methods() {
+            add_message(&method.input(), used);
+            add_message(&method.output(), used);
+            add_options(&method.options(), used);
+        }
+    }
+}
+
+/// Returns true if `file`, or any file it publicly imports, is in `used`.
+fn provides_used_file(file: &FileDescriptor, used: &HashSet<String>) -> bool {
+    used.contains(file.name())
+        || file
+            .public_dependencies()
+            .any(|dependency| provides_used_file(&dependency, used))
+}
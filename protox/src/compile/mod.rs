@@ -1,21 +1,38 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::{self, Write},
+    io,
     path::{Path, PathBuf},
 };
 
-use prost::Message;
-use prost_reflect::{DescriptorPool, DynamicMessage, ReflectMessage, Value};
-use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use miette::NamedSource;
+use prost::{
+    encoding::{self, WireType},
+    Message,
+};
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, ExtensionDescriptor, FieldDescriptor, FileDescriptor,
+    ReflectMessage, Value,
+};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    FileDescriptorSet, UninterpretedOption,
+};
+use protox_parse::Syntax;
 
+use self::lint::LintOptions;
 use crate::{
     error::{Error, ErrorKind},
-    file::{check_shadow, path_to_file_name, File, FileMetadata, FileResolver},
+    file::{check_shadow, path_to_file_name, File, FileMetadata, FileResolver, SymbolResolver},
+    warning::{LintLevel, Warning, WarningKind},
 };
 
+mod lint;
 #[cfg(test)]
 mod tests;
 
+type DescriptorHook = Box<dyn Fn(&mut FileDescriptorProto)>;
+
 /// Options for compiling protobuf files.
 ///
 /// # Examples
@@ -48,16 +65,34 @@ mod tests;
 pub struct Compiler {
     pool: DescriptorPool,
     resolver: Box<dyn FileResolver>,
+    symbol_resolver: Option<Box<dyn SymbolResolver>>,
+    well_known_imports: HashMap<String, String>,
     files: HashMap<String, FileMetadata>,
+    sources: HashMap<String, File>,
+    warnings: Vec<Warning>,
     include_imports: bool,
     include_source_info: bool,
+    stub_missing_imports: bool,
+    default_syntax: Option<Syntax>,
+    descriptor_hook: Option<DescriptorHook>,
+    max_total_input_bytes: Option<u64>,
+    total_input_bytes: u64,
+    option_limits: OptionLimits,
+    lints: LintOptions,
 }
 
 impl Compiler {
     /// Creates a new [`Compiler`] with default options and the given set of include paths.
     ///
-    /// In addition to the given include paths, the [`Compiler`] instance will be able to import
-    /// standard files like `google/protobuf/descriptor.proto`.
+    /// As with protoc's `--proto_path` flag, the include paths are searched in order, so a file under an earlier
+    /// path shadows a file with the same name under a later one. In addition to the given include paths, the
+    /// [`Compiler`] instance will be able to import standard files like `google/protobuf/descriptor.proto`.
+    ///
+    /// This is a shorthand for [`with_file_resolver`](Compiler::with_file_resolver) with a
+    /// [`ChainFileResolver`](crate::file::ChainFileResolver) of one
+    /// [`IncludeFileResolver`](crate::file::IncludeFileResolver) per include path, followed by a
+    /// [`GoogleFileResolver`](crate::file::GoogleFileResolver). Use `with_file_resolver` directly to load files
+    /// from other sources.
     pub fn new<I, P>(includes: I) -> Result<Self, Error>
     where
         I: IntoIterator<Item = P>,
@@ -84,9 +119,20 @@ impl Compiler {
         Compiler {
             pool: DescriptorPool::new(),
             resolver: Box::new(resolver),
+            symbol_resolver: None,
+            well_known_imports: HashMap::new(),
             files: HashMap::new(),
+            sources: HashMap::new(),
+            warnings: Vec::new(),
             include_imports: false,
             include_source_info: false,
+            stub_missing_imports: false,
+            default_syntax: Some(Syntax::Proto2),
+            descriptor_hook: None,
+            max_total_input_bytes: None,
+            total_input_bytes: 0,
+            option_limits: OptionLimits::default(),
+            lints: LintOptions::default(),
         }
     }
 
@@ -103,12 +149,146 @@ impl Compiler {
     /// Sets whether the output `FileDescriptorSet` should include imported files.
     ///
     /// By default, only files explicitly added with [`open_file`](Compiler::open_file) are returned by [`file_descriptor_set`](Compiler::file_descriptor_set).
-    /// If this option is set, imported files are included too.
+    /// If this option is set, imported files are included too, as with protoc's `--include_imports` flag.
+    ///
+    /// Imported files are always resolved and checked, whether or not they are included in the output. This option
+    /// is independent of [`include_source_info`](Compiler::include_source_info): if both are set, imported files
+    /// have source info too.
     pub fn include_imports(&mut self, yes: bool) -> &mut Self {
         self.include_imports = yes;
         self
     }
 
+    /// Sets whether imports of files which cannot be found should be replaced with empty files.
+    ///
+    /// If set, a warning is reported for each missing import instead of an error. The stubbed file defines no
+    /// symbols, so any reference to a type from it is still reported as an error where it is used.
+    pub fn stub_missing_imports(&mut self, yes: bool) -> &mut Self {
+        self.stub_missing_imports = yes;
+        self
+    }
+
+    /// Sets the syntax used for files which do not have a `syntax` statement.
+    ///
+    /// By default, such files are parsed as `proto2`, matching `protoc`. If set to `None`, files without a
+    /// `syntax` statement are rejected with an error.
+    ///
+    /// The syntax is passed to [`FileResolver::open_file_with_syntax`] when the compiler loads a file. Files which
+    /// are already parsed, such as those from a [`DescriptorSetFileResolver`](crate::file::DescriptorSetFileResolver),
+    /// are not affected.
+    pub fn default_syntax(&mut self, syntax: Option<Syntax>) -> &mut Self {
+        self.default_syntax = syntax;
+        self
+    }
+
+    /// Sets a limit on the combined size of all files opened by this compiler, including imports.
+    ///
+    /// If opening a file would cause the total size to exceed the limit, an error is returned instead. This
+    /// complements the limit on the size of a single file, and is useful when compiling untrusted input. Only
+    /// files which are added to this compiler stay counted, so a file which fails to compile can be retried
+    /// without counting it again.
+    pub fn max_total_input_bytes(&mut self, limit: u64) -> &mut Self {
+        self.max_total_input_bytes = Some(limit);
+        self
+    }
+
+    /// Sets a limit on the number of options set on a single declaration.
+    ///
+    /// If a file, message, field, enum, enum value, oneof, extension range, service or method sets more options than
+    /// the limit, an error is returned when the file is checked. This is useful when compiling untrusted input.
+    pub fn max_options_per_declaration(&mut self, limit: usize) -> &mut Self {
+        self.option_limits.max_options = Some(limit);
+        self
+    }
+
+    /// Sets a limit on the depth of nested messages in aggregate option values.
+    ///
+    /// An aggregate value such as `option (foo) = { bar: { baz: 1 } };` has a depth of two. If an option value is
+    /// nested more deeply than the limit, an error is returned when the file is checked. This is useful when
+    /// compiling untrusted input.
+    pub fn max_option_value_depth(&mut self, limit: u32) -> &mut Self {
+        self.option_limits.max_value_depth = Some(limit);
+        self
+    }
+
+    /// Sets whether to warn about 64-bit integer fields which do not set the `jstype` option.
+    ///
+    /// By default, 64-bit integers are represented as numbers in JavaScript, which cannot represent all values
+    /// precisely. If this lint is enabled, a [`Warning`] is reported for each `int64`, `uint64`, `sint64`, `fixed64`
+    /// and `sfixed64` field without an explicit `jstype`. Only proto3 files are checked, and fields of map entries are
+    /// not checked.
+    pub fn lint_jstype(&mut self, level: LintLevel) -> &mut Self {
+        self.lints.jstype = level;
+        self
+    }
+
+    /// Sets whether to warn about cycles of `required` message fields.
+    ///
+    /// A message containing a field which is part of such a cycle can never be fully initialized, since each
+    /// required field must be set to a message which itself has required fields. If this lint is enabled, a
+    /// [`Warning`] is reported for each field in the cycle.
+    pub fn lint_recursive_required_fields(&mut self, level: LintLevel) -> &mut Self {
+        self.lints.recursive_required_fields = level;
+        self
+    }
+
+    /// Sets whether to warn about imports which are not used.
+    ///
+    /// If this lint is enabled, a [`Warning`] is reported for each imported file which does not define any type,
+    /// extension or option referenced by the importing file. An import is considered used if a file it publicly
+    /// re-exports is used. The warnings do not cause compilation to fail.
+    pub fn lint_unused_imports(&mut self, level: LintLevel) -> &mut Self {
+        self.lints.unused_imports = level;
+        self
+    }
+
+    /// Sets a function which is called to modify each file descriptor before it is returned.
+    ///
+    /// The hook is invoked on each file returned by [`file_descriptor_set`](Compiler::file_descriptor_set),
+    /// [`compile_each`](Compiler::compile_each), [`encode_file_descriptor_set`](Compiler::encode_file_descriptor_set)
+    /// and [`into_code_generator_request`](Compiler::into_code_generator_request), in topological order. It does not
+    /// affect the descriptors in the [`descriptor_pool`](Compiler::descriptor_pool).
+    ///
+    /// The hook cannot see extension options, since they are not represented in [`FileDescriptorProto`]. They are
+    /// copied back into the output of [`encode_file_descriptor_set`](Compiler::encode_file_descriptor_set) and
+    /// [`compile_into`](Compiler::compile_into) for each definition which still has the same name and options after
+    /// the hook runs.
+    pub fn descriptor_hook<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&mut FileDescriptorProto) + 'static,
+    {
+        self.descriptor_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Sets a [`SymbolResolver`] used to locate files defining referenced types which are not imported.
+    ///
+    /// When a file references a fully-qualified type name (one starting with a `.`) that it does not define itself,
+    /// the symbol resolver is consulted, and the file it returns is added as an import. Relative type names are
+    /// not currently resolved this way.
+    pub fn symbol_resolver<S>(&mut self, resolver: S) -> &mut Self
+    where
+        S: SymbolResolver + 'static,
+    {
+        self.symbol_resolver = Some(Box::new(resolver));
+        self
+    }
+
+    /// Satisfies imports of a well-known file, such as `google/protobuf/timestamp.proto`, using a different file.
+    ///
+    /// When `name` is imported, the file `file_name` is opened with the [`FileResolver`] instead, but it is
+    /// still added to the output under the well-known name. This is useful in environments where the well-known
+    /// types are vendored under a non-standard path.
+    pub fn well_known_import(
+        &mut self,
+        name: impl Into<String>,
+        file_name: impl Into<String>,
+    ) -> &mut Self {
+        self.well_known_imports
+            .insert(name.into(), file_name.into());
+        self
+    }
+
     /// Compiles the file at the given path, and adds it to this `Compiler` instance.
     ///
     /// If the path is absolute, or relative to the current directory, it must reside under one of the
@@ -126,15 +306,58 @@ impl Compiler {
             }));
         };
 
+        self.add_root_file(name, path, is_resolved)?;
+        Ok(self)
+    }
+
+    /// Compiles every file which can be opened by the [`FileResolver`], and adds them to this `Compiler` instance.
+    ///
+    /// The files are listed with [`FileResolver::list_files`], and compiled in that order as if by
+    /// [`open_file()`][Compiler::open_file()]. This is useful for compiling all files under an include path
+    /// without naming them individually.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the resolver cannot list its files, or if any file fails to compile.
+    pub fn add_all_files(&mut self) -> Result<&mut Self, Error> {
+        let names = match self.resolver.list_files() {
+            Some(names) => names,
+            None => return Err(Error::from_kind(ErrorKind::ListFilesUnsupported)),
+        };
+
+        for name in names {
+            let path = PathBuf::from(&name);
+            self.add_root_file(name, &path, false)?;
+        }
+        Ok(self)
+    }
+
+    fn add_root_file(&mut self, name: String, path: &Path, is_resolved: bool) -> Result<(), Error> {
+        let result = self.add_root_file_inner(name, path, is_resolved);
+        if result.is_err() {
+            // Files opened for the failed file which were not added to the pool no longer count towards the limit,
+            // so the file can be added again after fixing the error.
+            self.total_input_bytes = self.files.values().map(|file| file.input_len).sum();
+        }
+        result
+    }
+
+    fn add_root_file_inner(
+        &mut self,
+        name: String,
+        path: &Path,
+        is_resolved: bool,
+    ) -> Result<(), Error> {
         if let Some(file_metadata) = self.files.get_mut(&name) {
             if is_resolved {
                 check_shadow(&name, file_metadata.path(), path)?;
             }
             file_metadata.is_import = false;
-            return Ok(self);
+            self.warnings.append(&mut file_metadata.import_warnings);
+            return Ok(());
         }
 
-        let file = self.resolver.open_file(&name).map_err(|err| {
+        let mut file = self.open_resolver_file(&name).map_err(|err| {
             if err.is_file_not_found() {
                 Error::from_kind(ErrorKind::FileNotIncluded {
                     path: path.to_owned(),
@@ -146,22 +369,47 @@ impl Compiler {
         if is_resolved {
             check_shadow(&name, file.path(), path)?;
         }
+        self.resolve_symbols(&mut file);
 
         let mut import_stack = vec![name.clone()];
-        for import in &file.descriptor.dependency {
-            self.add_import(import, &mut import_stack)?;
+        for (index, import) in file.descriptor.dependency.iter().enumerate() {
+            self.add_import(import, &file, index, &mut import_stack)?;
         }
         drop(import_stack);
 
-        let path = self.check_file(file)?;
-        self.files.insert(
-            name.clone(),
-            FileMetadata {
-                name,
-                path,
-                is_import: false,
-            },
-        );
+        self.check_file(name, file, false)
+    }
+
+    /// Compiles a file from the given source code, and adds it to this `Compiler` instance under `name`.
+    ///
+    /// The file does not need to exist on disk. Once added, it may also be imported by files added later, using
+    /// the same name. The source is parsed as if by [`File::from_source`], using the
+    /// [`default_syntax`](Compiler::default_syntax) of this compiler.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file fails to compile, or if a file with the same name has already been added or
+    /// is reported by [`FileResolver::contains_file`].
+    pub fn add_source(&mut self, name: &str, source: &str) -> Result<&mut Self, Error> {
+        let conflict = || {
+            Error::from_kind(ErrorKind::SourceConflict {
+                name: name.to_owned(),
+            })
+        };
+
+        if self.sources.contains_key(name)
+            || self.files.contains_key(name)
+            || self.resolver.contains_file(name)
+        {
+            return Err(conflict());
+        }
+
+        let file = File::from_source_with_syntax(name, source, self.default_syntax)?;
+        self.sources.insert(name.to_owned(), file);
+        if let Err(err) = self.add_root_file(name.to_owned(), Path::new(name), false) {
+            self.sources.remove(name);
+            return Err(err);
+        }
         Ok(self)
     }
 
@@ -179,6 +427,30 @@ impl Compiler {
         Ok(self)
     }
 
+    /// Checks the given files for errors, without stopping at the first file which fails to compile.
+    ///
+    /// This is intended for tools such as editors which only need diagnostics. Each file is compiled as if by
+    /// [`open_file()`][Compiler::open_file()], and any errors are returned instead of a descriptor set. The files
+    /// are not added to this `Compiler` instance: afterwards it contains the same files and
+    /// [`warnings`](Compiler::warnings) as before, so the same files can be checked again after fixing them.
+    pub fn check_only(&mut self, paths: impl IntoIterator<Item = impl AsRef<Path>>) -> Vec<Error> {
+        let pool = self.pool.clone();
+        let files = self.files.clone();
+        let warnings = self.warnings.len();
+        let total_input_bytes = self.total_input_bytes;
+
+        let errors = paths
+            .into_iter()
+            .filter_map(|path| self.open_file(path).err())
+            .collect();
+
+        self.pool = pool;
+        self.files = files;
+        self.warnings.truncate(warnings);
+        self.total_input_bytes = total_input_bytes;
+        errors
+    }
+
     /// Converts all added files into an instance of [`FileDescriptorSet`](prost_types::FileDescriptorSet).
     ///
     /// Files are sorted topologically, with dependency files ordered before the files that import them.
@@ -187,48 +459,75 @@ impl Compiler {
             .pool
             .files()
             .filter(|f| self.include_imports || !self.files[f.name()].is_import)
-            .map(|f| {
-                if self.include_source_info {
-                    f.file_descriptor_proto().clone()
-                } else {
-                    prost_types::FileDescriptorProto {
-                        source_code_info: None,
-                        ..f.file_descriptor_proto().clone()
-                    }
-                }
-            })
+            .map(|f| self.output_file(&f, self.include_source_info))
             .collect();
 
         prost_types::FileDescriptorSet { file }
     }
 
+    /// Gets the descriptors of all added files, without cloning them.
+    ///
+    /// Files are returned in the same topological order as [`file_descriptor_set`](Compiler::file_descriptor_set),
+    /// and files which were only imported are skipped unless [`include_imports`](Compiler::include_imports) is set.
+    /// Each [`FileDescriptor`] is a cheap handle into the compiled pool, so the name and
+    /// [`FileDescriptorProto`] of each file can be borrowed from it directly. Unlike
+    /// [`file_descriptor_set`](Compiler::file_descriptor_set), the descriptors always include source code info, and
+    /// the [`descriptor_hook`](Compiler::descriptor_hook) is not applied, and the dependencies may include additional
+    /// files as described for [`descriptor_pool`](Compiler::descriptor_pool).
+    pub fn file_descriptors(&self) -> impl Iterator<Item = FileDescriptor> + '_ {
+        self.pool
+            .files()
+            .filter(|f| self.include_imports || !self.files[f.name()].is_import)
+    }
+
+    /// Invokes the given callback with the name and descriptor of each added file.
+    ///
+    /// Files are visited in the same topological order as [`file_descriptor_set`](Compiler::file_descriptor_set),
+    /// and the [`include_imports`](Compiler::include_imports) and [`include_source_info`](Compiler::include_source_info)
+    /// options are respected, but the descriptors are passed to the callback one at a time instead of being collected
+    /// into a single set.
+    pub fn compile_each<F>(&self, mut f: F)
+    where
+        F: FnMut(&str, &FileDescriptorProto),
+    {
+        for file in self
+            .pool
+            .files()
+            .filter(|f| self.include_imports || !self.files[f.name()].is_import)
+        {
+            if self.include_source_info
+                && self.descriptor_hook.is_none()
+                && self.files[file.name()].hidden_dependencies == 0
+            {
+                f(file.name(), file.file_descriptor_proto());
+            } else {
+                f(
+                    file.name(),
+                    &self.output_file(&file, self.include_source_info),
+                );
+            }
+        }
+    }
+
     /// Converts all added files into an instance of [`FileDescriptorSet`](prost_types::FileDescriptorSet) and encodes it.
     ///
     /// This is equivalent to `file_descriptor_set()?.encode_to_vec()`, with the exception that extension
     /// options are included.
     pub fn encode_file_descriptor_set(&self) -> Vec<u8> {
-        if self.include_imports && self.include_source_info {
+        if self.descriptor_hook.is_none()
+            && self.include_imports
+            && self.include_source_info
+            && self.files.values().all(|f| f.hidden_dependencies == 0)
+        {
             // Avoid reflection if possible.
             return self.pool.encode_to_vec();
         }
 
-        let file_desc = FileDescriptorProto::default().descriptor();
-
         let files = self
             .pool
             .files()
             .filter(|f| self.include_imports || !self.files[f.name()].is_import)
-            .map(|f| {
-                let file_buf = f.encode_to_vec();
-
-                let mut file_msg =
-                    DynamicMessage::decode(file_desc.clone(), file_buf.as_slice()).unwrap();
-                if !self.include_source_info {
-                    file_msg.clear_field_by_name("source_code_info");
-                }
-
-                Value::Message(file_msg)
-            })
+            .map(|f| Value::Message(self.output_file_message(&f)))
             .collect();
 
         let mut file_descriptor_set = FileDescriptorSet::default().transcode_to_dynamic();
@@ -236,7 +535,110 @@ impl Compiler {
         file_descriptor_set.encode_to_vec()
     }
 
+    /// Encodes all added files as a [`FileDescriptorSet`](prost_types::FileDescriptorSet), writing each file to
+    /// `writer` as it is encoded.
+    ///
+    /// This produces the same bytes as [`encode_file_descriptor_set`](Compiler::encode_file_descriptor_set), but
+    /// avoids holding the whole encoded set in memory at once.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `writer` fails.
+    pub fn compile_into<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        const FILE_TAG: u32 = 1;
+
+        let mut header = Vec::new();
+        for f in self
+            .pool
+            .files()
+            .filter(|f| self.include_imports || !self.files[f.name()].is_import)
+        {
+            let file_buf = if self.descriptor_hook.is_none()
+                && self.include_source_info
+                && self.files[f.name()].hidden_dependencies == 0
+            {
+                f.encode_to_vec()
+            } else {
+                self.output_file_message(&f).encode_to_vec()
+            };
+
+            header.clear();
+            encoding::encode_key(FILE_TAG, WireType::LengthDelimited, &mut header);
+            encoding::encode_varint(file_buf.len() as u64, &mut header);
+            writer.write_all(&header)?;
+            writer.write_all(&file_buf)?;
+        }
+
+        Ok(())
+    }
+
+    /// Converts this `Compiler` into a [`CodeGeneratorRequest`](prost_types::compiler::CodeGeneratorRequest), suitable
+    /// for passing to a protoc plugin.
+    ///
+    /// The request contains the given files to generate, along with all of their transitive dependencies, sorted
+    /// topologically. As with protoc, source info is always included for plugins, regardless of the value of
+    /// [`include_source_info`](Compiler::include_source_info).
+    ///
+    /// Each name in `files_to_generate` must be the name of a file previously added to this compiler,
+    /// such as one returned by [`FileMetadata::name()`]. The `compiler_version` field of the request is set to the
+    /// version of protobuf whose behaviour this crate matches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a name in `files_to_generate` does not refer to a file added to this compiler.
+    pub fn into_code_generator_request(
+        self,
+        files_to_generate: &[String],
+        parameter: Option<String>,
+    ) -> Result<prost_types::compiler::CodeGeneratorRequest, Error> {
+        let mut required = HashSet::new();
+        let mut stack = files_to_generate
+            .iter()
+            .map(|name| {
+                self.pool
+                    .get_file_by_name(name)
+                    .ok_or_else(|| Error::file_not_found(name))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        while let Some(file) = stack.pop() {
+            if required.insert(file.name().to_owned()) {
+                stack.extend(file.dependencies());
+            }
+        }
+
+        let proto_file = self
+            .pool
+            .files()
+            .filter(|f| required.contains(f.name()))
+            .map(|f| self.output_file(&f, true))
+            .collect();
+
+        Ok(prost_types::compiler::CodeGeneratorRequest {
+            file_to_generate: files_to_generate.to_vec(),
+            parameter,
+            proto_file,
+            compiler_version: Some(prost_types::compiler::Version {
+                major: Some(3),
+                minor: Some(21),
+                patch: Some(5),
+                suffix: Some(String::new()),
+            }),
+        })
+    }
+
     /// Gets a copy of the [`DescriptorPool`] containing all referenced files.
+    ///
+    /// Files are added to the pool as they are checked, after all their imports, so this avoids building a new
+    /// pool from the output of [`file_descriptor_set`](Compiler::file_descriptor_set). The pool includes imported
+    /// files regardless of [`include_imports`](Compiler::include_imports).
+    ///
+    /// The pool only makes names visible through a single level of public imports. If one of the imports of a file
+    /// re-exports another file through more than one level of public imports, the descriptor of the file in the pool
+    /// lists that other file as an additional dependency after its own imports. These dependencies are omitted from
+    /// all other output.
     pub fn descriptor_pool(&self) -> DescriptorPool {
         self.pool.clone()
     }
@@ -248,7 +650,39 @@ impl Compiler {
         self.pool.files().map(|f| &self.files[f.name()])
     }
 
-    fn add_import(&mut self, file_name: &str, import_stack: &mut Vec<String>) -> Result<(), Error> {
+    /// Gets the warnings found in all added files.
+    ///
+    /// Warnings indicate problems which do not prevent a file from being compiled, such as a cycle of `required` message
+    /// fields.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Removes all files added to this compiler, so it can be reused to compile an unrelated set of files.
+    ///
+    /// This drops the compiled files, including those added with [`add_source`](Compiler::add_source), and any
+    /// warnings. The [`FileResolver`] and all options are kept, as is any state held by the resolver itself, such as
+    /// the files cached by a [`CachingFileResolver`](crate::file::CachingFileResolver). The total size counted
+    /// towards [`max_total_input_bytes`](Compiler::max_total_input_bytes) is reset.
+    pub fn clear(&mut self) -> &mut Self {
+        self.pool = DescriptorPool::new();
+        self.files.clear();
+        self.sources.clear();
+        self.warnings.clear();
+        self.total_input_bytes = 0;
+        self
+    }
+
+    fn add_import(
+        &mut self,
+        file_name: &str,
+        importer: &File,
+        index: usize,
+        import_stack: &mut Vec<String>,
+    ) -> Result<(), Error> {
+        /// The field number of `dependency` in `FileDescriptorProto`.
+        const DEPENDENCY: i32 = 3;
+
         if import_stack.iter().any(|name| name == file_name) {
             let mut cycle = String::new();
             for import in import_stack {
@@ -266,48 +700,908 @@ impl Compiler {
             return Ok(());
         }
 
-        let file = self.resolver.open_file(file_name)?;
+        let result = match self.well_known_imports.get(file_name).cloned() {
+            Some(mapped_name) => self
+                .open_resolver_file(&mapped_name)
+                .map(|file| file.clone_with_name(file_name)),
+            None => self.open_resolver_file(file_name),
+        };
+        let mut file = match result {
+            Ok(file) => file,
+            Err(err) if self.stub_missing_imports && err.is_file_not_found() => {
+                let source = importer.source();
+                self.warnings
+                    .push(Warning::from_kind(WarningKind::StubbedImport {
+                        name: importer.name().to_owned(),
+                        import: file_name.to_owned(),
+                        span: source.and_then(|source| {
+                            lint::source_span(
+                                &importer.descriptor,
+                                source,
+                                &[DEPENDENCY, index as i32],
+                            )
+                        }),
+                        source_code: source
+                            .map(|source| NamedSource::new(importer.name(), source.to_owned())),
+                    }));
+                File::from_file_descriptor_proto(FileDescriptorProto {
+                    name: Some(file_name.to_owned()),
+                    ..Default::default()
+                })
+            }
+            Err(err) if err.is_file_not_found() => {
+                return Err(Error::from_kind(ErrorKind::ImportNotFound {
+                    name: file_name.to_owned(),
+                    imported_by: import_stack.clone(),
+                }))
+            }
+            Err(err) => return Err(err),
+        };
+        self.resolve_symbols(&mut file);
 
         import_stack.push(file_name.to_owned());
-        for import in &file.descriptor.dependency {
-            self.add_import(import, import_stack)?;
+        for (index, import) in file.descriptor.dependency.iter().enumerate() {
+            self.add_import(import, &file, index, import_stack)?;
         }
         import_stack.pop();
 
-        let path = self.check_file(file)?;
-        self.files.insert(
-            file_name.to_owned(),
-            FileMetadata {
-                name: file_name.to_owned(),
-                path,
-                is_import: true,
-            },
-        );
-        Ok(())
+        self.check_file(file_name.to_owned(), file, true)
     }
 
-    fn check_file(
-        &mut self,
-        File {
+    fn output_file(&self, file: &FileDescriptor, include_source_info: bool) -> FileDescriptorProto {
+        let mut descriptor = file.file_descriptor_proto().clone();
+        let hidden_dependencies = self.files[file.name()].hidden_dependencies;
+        descriptor
+            .dependency
+            .truncate(descriptor.dependency.len() - hidden_dependencies);
+        if !include_source_info {
+            descriptor.source_code_info = None;
+        }
+        if let Some(hook) = &self.descriptor_hook {
+            hook(&mut descriptor);
+        }
+        descriptor
+    }
+
+    /// Gets the descriptor of a file as a dynamic message, which preserves extension options.
+    fn output_file_message(&self, file: &FileDescriptor) -> DynamicMessage {
+        if self.descriptor_hook.is_some() {
+            return self.output_hooked_file_message(file);
+        }
+
+        let mut file_msg = DynamicMessage::decode(
+            FileDescriptorProto::default().descriptor(),
+            file.encode_to_vec().as_slice(),
+        )
+        .unwrap();
+        if !self.include_source_info {
+            file_msg.clear_field_by_name("source_code_info");
+        }
+
+        let hidden_dependencies = self.files[file.name()].hidden_dependencies;
+        if hidden_dependencies != 0 {
+            let mut dependencies = file.file_descriptor_proto().dependency.clone();
+            dependencies.truncate(dependencies.len() - hidden_dependencies);
+            file_msg.set_field_by_name(
+                "dependency",
+                Value::List(dependencies.into_iter().map(Value::String).collect()),
+            );
+        }
+        file_msg
+    }
+
+    /// Applies the descriptor hook to a file, and copies the extension options of the original file into the result.
+    fn output_hooked_file_message(&self, file: &FileDescriptor) -> DynamicMessage {
+        // Extension options can only be set if the pool contains 'descriptor.proto', and decoding with its
+        // descriptor makes them available as extension fields.
+        let desc = self
+            .pool
+            .get_message_by_name("google.protobuf.FileDescriptorProto")
+            .unwrap_or_else(|| FileDescriptorProto::default().descriptor());
+
+        let original =
+            DynamicMessage::decode(desc.clone(), file.encode_to_vec().as_slice()).unwrap();
+        let mut extension_options = HashMap::new();
+        collect_extension_options(&original, "", &mut extension_options);
+
+        let mut file_msg = DynamicMessage::decode(
+            desc,
+            self.output_file(file, self.include_source_info)
+                .encode_to_vec()
+                .as_slice(),
+        )
+        .unwrap();
+        restore_extension_options(&mut file_msg, "", &extension_options);
+        file_msg
+    }
+
+    fn open_resolver_file(&mut self, name: &str) -> Result<File, Error> {
+        let file = match self.sources.get(name) {
+            Some(file) => file.clone(),
+            None => self
+                .resolver
+                .open_file_with_syntax(name, self.default_syntax)?,
+        };
+
+        if let Some(limit) = self.max_total_input_bytes {
+            self.total_input_bytes += input_len(&file);
+            if self.total_input_bytes > limit {
+                return Err(Error::from_kind(ErrorKind::TotalInputTooLarge {
+                    name: name.to_owned(),
+                    limit,
+                }));
+            }
+        }
+
+        Ok(file)
+    }
+
+    fn resolve_symbols(&self, file: &mut File) {
+        let symbol_resolver = match &self.symbol_resolver {
+            Some(symbol_resolver) => symbol_resolver,
+            None => return,
+        };
+
+        let mut defined = HashSet::new();
+        let mut referenced = Vec::new();
+        collect_symbols(&file.descriptor, &mut defined, &mut referenced);
+
+        let mut modified = false;
+        for name in referenced {
+            if defined.contains(&name) {
+                continue;
+            }
+
+            if let Some(dependency) = symbol_resolver.resolve_symbol(&name) {
+                if dependency != file.name() && !file.descriptor.dependency.contains(&dependency) {
+                    file.descriptor.dependency.push(dependency);
+                    modified = true;
+                }
+            }
+        }
+
+        if modified {
+            // The encoded bytes no longer match the descriptor.
+            file.encoded = None;
+        }
+    }
+
+    fn check_file(&mut self, name: String, file: File, is_import: bool) -> Result<(), Error> {
+        /// The field number of `dependency` in `FileDescriptorProto`.
+        const DEPENDENCY: u32 = 3;
+
+        let input_len = input_len(&file);
+        let File {
             path,
             source,
-            descriptor,
-            encoded,
-        }: File,
-    ) -> Result<Option<PathBuf>, Error> {
+            mut descriptor,
+            mut encoded,
+        } = file;
+
+        match &source {
+            // Like protoc, only reject an unnecessary `allow_alias` option in files parsed from source.
+            Some(source) => check_allow_alias(&descriptor, source)?,
+            None => {
+                // Files parsed from source always satisfy these, but descriptors from other sources may not.
+                check_names(&descriptor)?;
+                check_proto3_optional(&descriptor)?;
+            }
+        }
+        check_json_names(&descriptor, source.as_deref())?;
+        check_option_limits(&descriptor, self.option_limits)?;
+
+        // The descriptor pool only makes names from one level of public imports visible, so add any files which
+        // are imported publicly through more than one level as extra dependencies. They are removed again from
+        // the output.
+        let hidden_dependencies = self.indirect_public_dependencies(&descriptor);
+        for dependency in &hidden_dependencies {
+            if let Some(encoded) = &mut encoded {
+                // Repeated fields are appended to when decoding.
+                let mut buf = encoded.to_vec();
+                prost::encoding::string::encode(DEPENDENCY, dependency, &mut buf);
+                *encoded = buf.into();
+            }
+            descriptor.dependency.push(dependency.clone());
+        }
+
         if let Some(encoded) = &encoded {
             self.pool.decode_file_descriptor_proto(encoded.clone())
         } else {
             self.pool.add_file_descriptor_proto(descriptor)
         }
         .map_err(|mut err| {
-            if let Some(source) = source {
-                err = err.with_source_code(&source);
+            if let Some(source) = &source {
+                err = err.with_source_code(source);
             }
             err
         })?;
 
-        Ok(path)
+        // Imported files are only linted if they are later added explicitly.
+        let mut import_warnings = match self.pool.get_file_by_name(&name) {
+            Some(file) => lint::lint_file(
+                &file,
+                source.as_deref(),
+                hidden_dependencies.len(),
+                self.lints,
+            ),
+            None => Vec::new(),
+        };
+        if !is_import {
+            self.warnings.append(&mut import_warnings);
+        }
+
+        self.files.insert(
+            name.clone(),
+            FileMetadata {
+                name,
+                path,
+                is_import,
+                hidden_dependencies: hidden_dependencies.len(),
+                import_warnings,
+                input_len,
+            },
+        );
+        Ok(())
+    }
+
+    /// Gets the files which are imported publicly by a dependency of `file` through two or more levels of public
+    /// imports, and are not already direct dependencies of it.
+    fn indirect_public_dependencies(&self, file: &FileDescriptorProto) -> Vec<String> {
+        let mut seen: HashSet<String> = file.dependency.iter().cloned().collect();
+        let mut stack = Vec::new();
+        for dependency in &file.dependency {
+            if let Some(dependency) = self.pool.get_file_by_name(dependency) {
+                for public in dependency.public_dependencies() {
+                    if seen.insert(public.name().to_owned()) {
+                        stack.push(public);
+                    }
+                }
+            }
+        }
+
+        let mut indirect = Vec::new();
+        while let Some(public) = stack.pop() {
+            for public in public.public_dependencies() {
+                if seen.insert(public.name().to_owned()) {
+                    indirect.push(public.name().to_owned());
+                    stack.push(public);
+                }
+            }
+        }
+        indirect
+    }
+}
+
+fn collect_symbols(
+    file: &FileDescriptorProto,
+    defined: &mut HashSet<String>,
+    referenced: &mut Vec<String>,
+) {
+    fn add_reference(referenced: &mut Vec<String>, name: &str) {
+        if let Some(name) = name.strip_prefix('.') {
+            referenced.push(name.to_owned());
+        }
+    }
+
+    fn visit_message(
+        message: &DescriptorProto,
+        scope: &str,
+        defined: &mut HashSet<String>,
+        referenced: &mut Vec<String>,
+    ) {
+        let name = join_name(scope, message.name());
+        for field in message.field.iter().chain(&message.extension) {
+            add_reference(referenced, field.type_name());
+            add_reference(referenced, field.extendee());
+        }
+        for enum_type in &message.enum_type {
+            defined.insert(join_name(&name, enum_type.name()));
+        }
+        for nested in &message.nested_type {
+            visit_message(nested, &name, defined, referenced);
+        }
+        defined.insert(name);
+    }
+
+    let package = file.package();
+    for message in &file.message_type {
+        visit_message(message, package, defined, referenced);
+    }
+    for enum_type in &file.enum_type {
+        defined.insert(join_name(package, enum_type.name()));
+    }
+    for extension in &file.extension {
+        add_reference(referenced, extension.type_name());
+        add_reference(referenced, extension.extendee());
+    }
+    for service in &file.service {
+        for method in &service.method {
+            add_reference(referenced, method.input_type());
+            add_reference(referenced, method.output_type());
+        }
+    }
+}
+
+fn check_names(file: &FileDescriptorProto) -> Result<(), Error> {
+    fn check(file: &FileDescriptorProto, kind: &'static str, value: &str) -> Result<(), Error> {
+        if is_valid_name(value) {
+            Ok(())
+        } else {
+            Err(Error::from_kind(ErrorKind::InvalidName {
+                name: file.name().to_owned(),
+                kind,
+                value: value.to_owned(),
+            }))
+        }
+    }
+
+    fn check_enum(file: &FileDescriptorProto, enum_: &EnumDescriptorProto) -> Result<(), Error> {
+        check(file, "enum", enum_.name())?;
+        for value in &enum_.value {
+            check(file, "enum value", value.name())?;
+        }
+        Ok(())
+    }
+
+    fn check_message(file: &FileDescriptorProto, message: &DescriptorProto) -> Result<(), Error> {
+        check(file, "message", message.name())?;
+        for field in message.field.iter().chain(&message.extension) {
+            check(file, "field", field.name())?;
+        }
+        for oneof in &message.oneof_decl {
+            check(file, "oneof", oneof.name())?;
+        }
+        for nested in &message.nested_type {
+            check_message(file, nested)?;
+        }
+        for enum_ in &message.enum_type {
+            check_enum(file, enum_)?;
+        }
+        Ok(())
+    }
+
+    let package = file.package();
+    if !package.is_empty() && !package.split('.').all(is_valid_name) {
+        return Err(Error::from_kind(ErrorKind::InvalidName {
+            name: file.name().to_owned(),
+            kind: "package",
+            value: package.to_owned(),
+        }));
+    }
+    for message in &file.message_type {
+        check_message(file, message)?;
+    }
+    for enum_ in &file.enum_type {
+        check_enum(file, enum_)?;
+    }
+    for extension in &file.extension {
+        check(file, "field", extension.name())?;
+    }
+    for service in &file.service {
+        check(file, "service", service.name())?;
+        for method in &service.method {
+            check(file, "method", method.name())?;
+        }
+    }
+    Ok(())
+}
+
+fn check_proto3_optional(file: &FileDescriptorProto) -> Result<(), Error> {
+    fn check_message(file: &str, message: &DescriptorProto, scope: &str) -> Result<(), Error> {
+        let name = join_name(scope, message.name());
+        for field in &message.field {
+            if !field.proto3_optional() {
+                continue;
+            }
+
+            let is_valid = match field.oneof_index {
+                Some(index) if index >= 0 && (index as usize) < message.oneof_decl.len() => {
+                    message
+                        .field
+                        .iter()
+                        .filter(|f| f.oneof_index == Some(index))
+                        .count()
+                        == 1
+                }
+                _ => false,
+            };
+            if !is_valid {
+                return Err(Error::from_kind(ErrorKind::InvalidProto3Optional {
+                    name: file.to_owned(),
+                    field: join_name(&name, field.name()),
+                }));
+            }
+        }
+
+        for nested in &message.nested_type {
+            check_message(file, nested, &name)?;
+        }
+        Ok(())
+    }
+
+    for message in &file.message_type {
+        check_message(file.name(), message, file.package())?;
+    }
+    Ok(())
+}
+
+fn check_json_names(file: &FileDescriptorProto, source: Option<&str>) -> Result<(), Error> {
+    /// The field numbers of `message_type` in `FileDescriptorProto`, and of `field` and `nested_type` in
+    /// `DescriptorProto`.
+    const MESSAGE_TYPE: i32 = 4;
+    const FIELD: i32 = 2;
+    const NESTED_TYPE: i32 = 3;
+
+    fn check_message(
+        file: &FileDescriptorProto,
+        source: Option<&str>,
+        message: &DescriptorProto,
+        scope: &str,
+        path: &mut Vec<i32>,
+    ) -> Result<(), Error> {
+        let name = join_name(scope, message.name());
+
+        let mut json_names: HashMap<String, usize> = HashMap::new();
+        for (index, field) in message.field.iter().enumerate() {
+            let json_name = match &field.json_name {
+                Some(json_name) => json_name.clone(),
+                None => to_json_name(field.name()),
+            };
+
+            if let Some(&first) = json_names.get(&json_name) {
+                let span = |index: usize| {
+                    let field_path = [path.as_slice(), &[FIELD, index as i32, 1]].concat();
+                    source.and_then(|source| lint::source_span(file, source, &field_path))
+                };
+
+                return Err(Error::from_kind(ErrorKind::JsonNameConflict {
+                    name: file.name().to_owned(),
+                    json_name,
+                    first_field: join_name(&name, message.field[first].name()),
+                    second_field: join_name(&name, field.name()),
+                    first: span(first),
+                    second: span(index),
+                    source_code: source
+                        .map(|source| NamedSource::new(file.name(), source.to_owned())),
+                }));
+            }
+            json_names.insert(json_name, index);
+        }
+
+        for (index, nested) in message.nested_type.iter().enumerate() {
+            path.extend([NESTED_TYPE, index as i32]);
+            check_message(file, source, nested, &name, path)?;
+            path.truncate(path.len() - 2);
+        }
+        Ok(())
+    }
+
+    for (index, message) in file.message_type.iter().enumerate() {
+        check_message(
+            file,
+            source,
+            message,
+            file.package(),
+            &mut vec![MESSAGE_TYPE, index as i32],
+        )?;
+    }
+    Ok(())
+}
+
+/// Limits on the options of a single declaration, checked before the options are interpreted.
+#[derive(Debug, Default, Clone, Copy)]
+struct OptionLimits {
+    max_options: Option<usize>,
+    max_value_depth: Option<u32>,
+}
+
+fn check_option_limits(file: &FileDescriptorProto, limits: OptionLimits) -> Result<(), Error> {
+    fn check(
+        file: &FileDescriptorProto,
+        limits: OptionLimits,
+        declaration: &str,
+        options: &[UninterpretedOption],
+    ) -> Result<(), Error> {
+        if let Some(limit) = limits.max_options {
+            if options.len() > limit {
+                return Err(Error::from_kind(ErrorKind::TooManyOptions {
+                    name: file.name().to_owned(),
+                    declaration: declaration.to_owned(),
+                    count: options.len(),
+                    limit,
+                }));
+            }
+        }
+        if let Some(limit) = limits.max_value_depth {
+            if options.iter().any(|option| {
+                option
+                    .aggregate_value
+                    .as_deref()
+                    .is_some_and(|value| aggregate_depth(value) > limit)
+            }) {
+                return Err(Error::from_kind(ErrorKind::OptionValueTooDeep {
+                    name: file.name().to_owned(),
+                    declaration: declaration.to_owned(),
+                    limit,
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_field(
+        file: &FileDescriptorProto,
+        limits: OptionLimits,
+        scope: &str,
+        field: &FieldDescriptorProto,
+    ) -> Result<(), Error> {
+        if let Some(options) = &field.options {
+            let name = join_name(scope, field.name());
+            check(file, limits, &name, &options.uninterpreted_option)?;
+        }
+        Ok(())
+    }
+
+    fn check_enum(
+        file: &FileDescriptorProto,
+        limits: OptionLimits,
+        scope: &str,
+        enum_: &EnumDescriptorProto,
+    ) -> Result<(), Error> {
+        let name = join_name(scope, enum_.name());
+        if let Some(options) = &enum_.options {
+            check(file, limits, &name, &options.uninterpreted_option)?;
+        }
+        for value in &enum_.value {
+            if let Some(options) = &value.options {
+                let value_name = join_name(&name, value.name());
+                check(file, limits, &value_name, &options.uninterpreted_option)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_message(
+        file: &FileDescriptorProto,
+        limits: OptionLimits,
+        scope: &str,
+        message: &DescriptorProto,
+    ) -> Result<(), Error> {
+        let name = join_name(scope, message.name());
+        if let Some(options) = &message.options {
+            check(file, limits, &name, &options.uninterpreted_option)?;
+        }
+        for field in message.field.iter().chain(&message.extension) {
+            check_field(file, limits, &name, field)?;
+        }
+        for oneof in &message.oneof_decl {
+            if let Some(options) = &oneof.options {
+                let oneof_name = join_name(&name, oneof.name());
+                check(file, limits, &oneof_name, &options.uninterpreted_option)?;
+            }
+        }
+        for range in &message.extension_range {
+            if let Some(options) = &range.options {
+                check(file, limits, &name, &options.uninterpreted_option)?;
+            }
+        }
+        for nested in &message.nested_type {
+            check_message(file, limits, &name, nested)?;
+        }
+        for enum_ in &message.enum_type {
+            check_enum(file, limits, &name, enum_)?;
+        }
+        Ok(())
+    }
+
+    if limits.max_options.is_none() && limits.max_value_depth.is_none() {
+        return Ok(());
+    }
+
+    let package = file.package();
+    if let Some(options) = &file.options {
+        check(file, limits, file.name(), &options.uninterpreted_option)?;
+    }
+    for message in &file.message_type {
+        check_message(file, limits, package, message)?;
+    }
+    for enum_ in &file.enum_type {
+        check_enum(file, limits, package, enum_)?;
+    }
+    for extension in &file.extension {
+        check_field(file, limits, package, extension)?;
+    }
+    for service in &file.service {
+        let name = join_name(package, service.name());
+        if let Some(options) = &service.options {
+            check(file, limits, &name, &options.uninterpreted_option)?;
+        }
+        for method in &service.method {
+            if let Some(options) = &method.options {
+                let method_name = join_name(&name, method.name());
+                check(file, limits, &method_name, &options.uninterpreted_option)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Gets the depth of nested messages in an aggregate option value, ignoring delimiters in string literals.
+///
+/// The value does not include its outer braces, but is counted as one level itself.
+fn aggregate_depth(value: &str) -> u32 {
+    let mut depth = 1u32;
+    let mut max_depth = 1;
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' | '<' => {
+                depth += 1;
+                max_depth = max_depth.max(depth);
+            }
+            '}' | '>' => depth = depth.saturating_sub(1),
+            '"' | '\'' => {
+                while let Some(next) = chars.next() {
+                    if next == '\\' {
+                        chars.next();
+                    } else if next == ch {
+                        break;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    max_depth
+}
+
+/// Returns true if `name` is a valid identifier: a letter or underscore followed by letters, numbers and underscores.
+fn is_valid_name(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_')
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
+/// Returns the size of a file counted towards [`Compiler::max_total_input_bytes`].
+fn input_len(file: &File) -> u64 {
+    let len = match (&file.source, &file.encoded) {
+        (Some(source), _) => source.len(),
+        (None, Some(encoded)) => encoded.len(),
+        (None, None) => file.descriptor.encoded_len(),
+    };
+    len as u64
+}
+
+/// Converts a field name to its default JSON name, using the same rules as protoc.
+fn to_json_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut uppercase_next = false;
+    for ch in name.chars() {
+        if ch == '_' {
+            uppercase_next = true;
+        } else if uppercase_next {
+            result.push(ch.to_ascii_uppercase());
+            uppercase_next = false;
+        } else {
+            result.push(ch);
+        }
+    }
+    result
+}
+
+fn check_allow_alias(file: &FileDescriptorProto, source: &str) -> Result<(), Error> {
+    /// The field numbers of `message_type` and `enum_type` in `FileDescriptorProto`, and of `nested_type` and
+    /// `enum_type` in `DescriptorProto`.
+    const MESSAGE_TYPE: i32 = 4;
+    const FILE_ENUM_TYPE: i32 = 5;
+    const NESTED_TYPE: i32 = 3;
+    const MESSAGE_ENUM_TYPE: i32 = 4;
+
+    fn check_enum(
+        file: &FileDescriptorProto,
+        source: &str,
+        enum_: &EnumDescriptorProto,
+        scope: &str,
+        path: &[i32],
+    ) -> Result<(), Error> {
+        /// The field numbers of `value` in `EnumDescriptorProto` and of `number` in `EnumValueDescriptorProto`.
+        const VALUE: i32 = 2;
+        const NUMBER: i32 = 2;
+
+        let allow_alias = enum_.options.as_ref().and_then(|o| {
+            o.allow_alias.or_else(|| {
+                o.uninterpreted_option
+                    .iter()
+                    .find_map(|option| match option.name.as_slice() {
+                        [part] if part.name_part == "allow_alias" && !part.is_extension => {
+                            Some(option.identifier_value() == "true")
+                        }
+                        _ => None,
+                    })
+            })
+        });
+
+        let mut numbers = HashMap::new();
+        let mut has_alias = false;
+        for (index, value) in enum_.value.iter().enumerate() {
+            let Some(&first) = numbers.get(&value.number()) else {
+                numbers.insert(value.number(), index);
+                continue;
+            };
+
+            if allow_alias != Some(true) {
+                let span = |index: usize| {
+                    lint::source_span(
+                        file,
+                        source,
+                        &[path, &[VALUE, index as i32, NUMBER]].concat(),
+                    )
+                };
+
+                return Err(Error::from_kind(
+                    ErrorKind::DuplicateEnumValueNumberWithoutAlias {
+                        name: file.name().to_owned(),
+                        enum_name: join_name(scope, enum_.name()),
+                        number: value.number(),
+                        first_value: enum_.value[first].name().to_owned(),
+                        second_value: value.name().to_owned(),
+                        first: span(first),
+                        second: span(index),
+                        source_code: Some(NamedSource::new(file.name(), source.to_owned())),
+                    },
+                ));
+            }
+            has_alias = true;
+        }
+
+        // Like protoc, reject the option if it has no effect, whether it is set to true or false.
+        if allow_alias.is_some() && !has_alias {
+            // Point at the name of the enum.
+            let name_path = [path, &[1]].concat();
+            return Err(Error::from_kind(ErrorKind::UnnecessaryAllowAlias {
+                name: file.name().to_owned(),
+                enum_name: join_name(scope, enum_.name()),
+                span: lint::source_span(file, source, &name_path),
+                source_code: Some(NamedSource::new(file.name(), source.to_owned())),
+            }));
+        }
+        Ok(())
+    }
+
+    fn check_message(
+        file: &FileDescriptorProto,
+        source: &str,
+        message: &DescriptorProto,
+        scope: &str,
+        path: &mut Vec<i32>,
+    ) -> Result<(), Error> {
+        let name = join_name(scope, message.name());
+        for (index, enum_) in message.enum_type.iter().enumerate() {
+            let enum_path = [path.as_slice(), &[MESSAGE_ENUM_TYPE, index as i32]].concat();
+            check_enum(file, source, enum_, &name, &enum_path)?;
+        }
+        for (index, nested) in message.nested_type.iter().enumerate() {
+            path.extend([NESTED_TYPE, index as i32]);
+            check_message(file, source, nested, &name, path)?;
+            path.truncate(path.len() - 2);
+        }
+        Ok(())
+    }
+
+    for (index, enum_) in file.enum_type.iter().enumerate() {
+        check_enum(
+            file,
+            source,
+            enum_,
+            file.package(),
+            &[FILE_ENUM_TYPE, index as i32],
+        )?;
+    }
+    for (index, message) in file.message_type.iter().enumerate() {
+        check_message(
+            file,
+            source,
+            message,
+            file.package(),
+            &mut vec![MESSAGE_TYPE, index as i32],
+        )?;
+    }
+    Ok(())
+}
+
+/// Collects the extension fields set on each options message in a descriptor, keyed by the names of the
+/// definitions containing it.
+fn collect_extension_options(
+    message: &DynamicMessage,
+    key: &str,
+    extension_options: &mut HashMap<String, Vec<(ExtensionDescriptor, Value)>>,
+) {
+    for (field, value) in message.fields() {
+        match value {
+            Value::Message(options) if field.name() == "options" => {
+                let extensions: Vec<_> = options
+                    .extensions()
+                    .map(|(extension, value)| (extension, value.clone()))
+                    .collect();
+                if !extensions.is_empty() {
+                    extension_options.insert(key.to_owned(), extensions);
+                }
+            }
+            Value::Message(child) => collect_extension_options(
+                child,
+                &child_key(key, &field, None, child),
+                extension_options,
+            ),
+            Value::List(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    if let Value::Message(child) = value {
+                        collect_extension_options(
+                            child,
+                            &child_key(key, &field, Some(index), child),
+                            extension_options,
+                        );
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Sets the extension fields collected by [`collect_extension_options`] on each options message with the same key.
+fn restore_extension_options(
+    message: &mut DynamicMessage,
+    key: &str,
+    extension_options: &HashMap<String, Vec<(ExtensionDescriptor, Value)>>,
+) {
+    for (field, value) in message.fields_mut() {
+        match value {
+            Value::Message(options) if field.name() == "options" => {
+                for (extension, value) in extension_options.get(key).into_iter().flatten() {
+                    options.set_extension(extension, value.clone());
+                }
+            }
+            Value::Message(child) => {
+                let child_key = child_key(key, &field, None, child);
+                restore_extension_options(child, &child_key, extension_options);
+            }
+            Value::List(values) => {
+                for (index, value) in values.iter_mut().enumerate() {
+                    if let Value::Message(child) = value {
+                        let child_key = child_key(key, &field, Some(index), child);
+                        restore_extension_options(child, &child_key, extension_options);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Identifies a nested definition by its name if it has one, so that its key does not change if the descriptor
+/// hook reorders its siblings.
+fn child_key(
+    key: &str,
+    field: &FieldDescriptor,
+    index: Option<usize>,
+    child: &DynamicMessage,
+) -> String {
+    let name = child
+        .get_field_by_name("name")
+        .filter(|_| child.has_field_by_name("name"));
+    match (name.as_deref().and_then(Value::as_str), index) {
+        (Some(name), _) => format!("{}/{}:{}", key, field.name(), name),
+        (None, Some(index)) => format!("{}/{}[{}]", key, field.name(), index),
+        (None, None) => format!("{}/{}", key, field.name()),
+    }
+}
+
+fn join_name(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", scope, name)
     }
 }
 
@@ -646,6 +646,42 @@ fn invalid_file() {
     }
 }
 
+#[test]
+fn duplicate_enum_value_number_without_alias() {
+    let dir = TempDir::new().unwrap();
+
+    std::fs::write(
+        dir.path().join("foo.proto"),
+        "package pkg; message Foo { enum Bar { ZERO = 0; ONE = 1; ALIAS = 1; } }",
+    )
+    .unwrap();
+
+    let mut compiler = Compiler::new(once(&dir)).unwrap();
+    let err = compiler.open_file("foo.proto").unwrap_err();
+
+    match err.kind() {
+        ErrorKind::DuplicateEnumValueNumberWithoutAlias {
+            name,
+            enum_name,
+            number,
+            first_value,
+            second_value,
+            first,
+            second,
+            ..
+        } => {
+            assert_eq!(name, "foo.proto");
+            assert_eq!(enum_name, "pkg.Foo.Bar");
+            assert_eq!(*number, 1);
+            assert_eq!(first_value, "ONE");
+            assert_eq!(second_value, "ALIAS");
+            assert_eq!(*first, Some((54, 1).into()));
+            assert_eq!(*second, Some((65, 1).into()));
+        }
+        kind => panic!("unexpected error: {}", kind),
+    }
+}
+
 #[test]
 fn shadow_file_rel() {
     let dir = TempDir::new().unwrap();
@@ -891,6 +927,25 @@ fn import_cycle() {
     }
 }
 
+#[test]
+fn import_cycle_two_files() {
+    let dir = TempDir::new().unwrap();
+
+    std::fs::write(dir.path().join("a.proto"), "import 'b.proto';").unwrap();
+    std::fs::write(dir.path().join("b.proto"), "import 'a.proto';").unwrap();
+
+    let mut compiler = Compiler::new([dir.path()]).unwrap();
+    let err = compiler.open_file("a.proto").unwrap_err();
+
+    match err.kind() {
+        ErrorKind::CircularImport { name, cycle } => {
+            assert_eq!(name, "a.proto");
+            assert_eq!(cycle, "a.proto -> b.proto -> a.proto")
+        }
+        kind => panic!("unexpected error: {}", kind),
+    }
+}
+
 #[test]
 fn import_cycle_short() {
     let dir = TempDir::new().unwrap();
@@ -902,9 +957,10 @@ fn import_cycle_short() {
     let err = compiler.open_file("root.proto").unwrap_err();
 
     match err.kind() {
-        ErrorKind::CircularImport { name, cycle } => {
-            assert_eq!(name, "dep.proto");
-            assert_eq!(cycle, "root.proto -> dep.proto -> dep.proto")
+        ErrorKind::Parse { err } => {
+            assert_eq!(err.file(), "dep.proto");
+            assert_eq!(err.to_string(), "a file cannot import itself");
+            assert_eq!(err.span(), Some(7..18));
         }
         kind => panic!("unexpected error: {}", kind),
     }
@@ -920,9 +976,10 @@ fn import_cycle_nested() {
     let err = compiler.open_file("root.proto").unwrap_err();
 
     match err.kind() {
-        ErrorKind::CircularImport { name, cycle } => {
-            assert_eq!(name, "root.proto");
-            assert_eq!(cycle, "root.proto -> root.proto")
+        ErrorKind::Parse { err } => {
+            assert_eq!(err.file(), "root.proto");
+            assert_eq!(err.to_string(), "a file cannot import itself");
+            assert_eq!(err.span(), Some(7..19));
         }
         kind => panic!("unexpected error: {}", kind),
     }
@@ -1,6 +1,7 @@
-use std::{fmt, io, path::PathBuf};
+use std::{fmt, io, ops::Range, path::PathBuf};
 
-use miette::Diagnostic;
+use miette::{Diagnostic, NamedSource, SourceCode, SourceSpan};
+use prost::DecodeError;
 use prost_reflect::DescriptorError;
 use protox_parse::ParseError;
 use thiserror::Error;
@@ -13,6 +14,18 @@ pub struct Error {
     kind: Box<ErrorKind>,
 }
 
+/// The stage of compilation at which an [`struct@Error`] occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Phase {
+    /// The error occurred while locating or opening a file.
+    Resolve,
+    /// The error occurred while parsing a source file.
+    Parse,
+    /// The error occurred while checking a parsed file, for example due to an undefined type name.
+    Check,
+}
+
 #[derive(Debug, Diagnostic, Error)]
 pub(crate) enum ErrorKind {
     #[error("{}", err)]
@@ -28,13 +41,24 @@ pub(crate) enum ErrorKind {
         #[source]
         err: io::Error,
     },
+    #[error("file '{path}' is not a valid file descriptor set")]
+    InvalidFileDescriptorSet {
+        path: PathBuf,
+        #[source]
+        err: DecodeError,
+    },
     #[error("file '{name}' is too large")]
     #[diagnostic(help("the maximum file length is 2,147,483,647 bytes"))]
     FileTooLarge { name: String },
+    #[error("the total size of input files exceeds the limit of {limit} bytes")]
+    TotalInputTooLarge { name: String, limit: u64 },
     #[error("file '{name}' is not valid utf-8")]
     FileInvalidUtf8 { name: String },
-    #[error("import '{name}' not found")]
-    ImportNotFound { name: String },
+    #[error("import '{name}' not found{}", fmt_import_chain(.imported_by))]
+    ImportNotFound {
+        name: String,
+        imported_by: Vec<String>,
+    },
     #[error("import cycle detected: {cycle}")]
     CircularImport { name: String, cycle: String },
     #[error("file '{path}' is not in any include path")]
@@ -46,6 +70,91 @@ pub(crate) enum ErrorKind {
         path: PathBuf,
         shadow: PathBuf,
     },
+    #[error("file '{name}' can be opened from multiple locations with different contents")]
+    FileConflict { name: String },
+    #[error("file '{name}' cannot be added from source, because a file with the same name already exists")]
+    SourceConflict { name: String },
+    #[error("the file resolver cannot list its files")]
+    ListFilesUnsupported,
+    #[cfg(feature = "git")]
+    #[error("git error: {message}")]
+    Git { message: String },
+    #[cfg(feature = "git")]
+    #[error("failed to fetch git repository '{url}': {message}")]
+    GitNetwork { url: String, message: String },
+    #[cfg(feature = "git")]
+    #[error("authentication failed for git repository '{url}': {message}")]
+    GitAuthentication { url: String, message: String },
+    #[cfg(feature = "git")]
+    #[error("reference '{reference}' not found in git repository")]
+    GitReferenceNotFound { reference: String },
+    #[error("field '{field}' has proto3_optional set, but is not the only member of a oneof")]
+    InvalidProto3Optional { name: String, field: String },
+    #[error("invalid {kind} name '{value}'")]
+    #[diagnostic(help(
+        "names must consist of letters, numbers and underscores, and may not start with a number"
+    ))]
+    InvalidName {
+        name: String,
+        kind: &'static str,
+        value: String,
+    },
+    #[error("'{declaration}' has {count} options, which exceeds the limit of {limit}")]
+    TooManyOptions {
+        name: String,
+        declaration: String,
+        count: usize,
+        limit: usize,
+    },
+    #[error("an option value of '{declaration}' is nested more than {limit} levels deep")]
+    OptionValueTooDeep {
+        name: String,
+        declaration: String,
+        limit: u32,
+    },
+    #[error("enum '{enum_name}' sets the 'allow_alias' option, but no enum values share a number")]
+    #[diagnostic(help("remove the unnecessary 'allow_alias' option"))]
+    UnnecessaryAllowAlias {
+        name: String,
+        enum_name: String,
+        #[label("defined here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        source_code: Option<NamedSource<String>>,
+    },
+    #[error("enum value '{second_value}' uses the same number '{number}' as '{first_value}'")]
+    #[diagnostic(help(
+        "set 'option allow_alias = true;' on enum '{enum_name}' if this is intended"
+    ))]
+    DuplicateEnumValueNumberWithoutAlias {
+        name: String,
+        enum_name: String,
+        number: i32,
+        first_value: String,
+        second_value: String,
+        #[label("first used here")]
+        first: Option<SourceSpan>,
+        #[label("used again here")]
+        second: Option<SourceSpan>,
+        #[source_code]
+        source_code: Option<NamedSource<String>>,
+    },
+    #[error(
+        "field '{second_field}' has the same JSON name '{json_name}' as field '{first_field}'"
+    )]
+    #[diagnostic(help("set a different 'json_name' option on one of the fields"))]
+    JsonNameConflict {
+        name: String,
+        json_name: String,
+        first_field: String,
+        second_field: String,
+        #[label("first defined here")]
+        first: Option<SourceSpan>,
+        #[label("defined again here")]
+        second: Option<SourceSpan>,
+        #[source_code]
+        source_code: Option<NamedSource<String>>,
+    },
     #[error(transparent)]
     Custom(Box<dyn std::error::Error + Send + Sync>),
 }
@@ -65,6 +174,7 @@ impl Error {
     pub fn file_not_found(name: &str) -> Self {
         Error::from_kind(ErrorKind::ImportNotFound {
             name: name.to_owned(),
+            imported_by: Vec::new(),
         })
     }
 
@@ -76,10 +186,27 @@ impl Error {
             ErrorKind::OpenFile { name, .. }
             | ErrorKind::FileTooLarge { name }
             | ErrorKind::FileInvalidUtf8 { name }
-            | ErrorKind::ImportNotFound { name }
+            | ErrorKind::ImportNotFound { name, .. }
             | ErrorKind::CircularImport { name, .. }
-            | ErrorKind::FileShadowed { name, .. } => Some(name),
-            ErrorKind::FileNotIncluded { .. } => None,
+            | ErrorKind::FileShadowed { name, .. }
+            | ErrorKind::FileConflict { name }
+            | ErrorKind::SourceConflict { name }
+            | ErrorKind::TotalInputTooLarge { name, .. }
+            | ErrorKind::InvalidProto3Optional { name, .. }
+            | ErrorKind::InvalidName { name, .. }
+            | ErrorKind::TooManyOptions { name, .. }
+            | ErrorKind::OptionValueTooDeep { name, .. }
+            | ErrorKind::UnnecessaryAllowAlias { name, .. }
+            | ErrorKind::DuplicateEnumValueNumberWithoutAlias { name, .. }
+            | ErrorKind::JsonNameConflict { name, .. } => Some(name),
+            ErrorKind::FileNotIncluded { .. }
+            | ErrorKind::InvalidFileDescriptorSet { .. }
+            | ErrorKind::ListFilesUnsupported => None,
+            #[cfg(feature = "git")]
+            ErrorKind::Git { .. }
+            | ErrorKind::GitNetwork { .. }
+            | ErrorKind::GitAuthentication { .. }
+            | ErrorKind::GitReferenceNotFound { .. } => None,
             ErrorKind::Custom(_) => None,
         }
     }
@@ -95,6 +222,108 @@ impl Error {
         &self.kind
     }
 
+    /// The stage of compilation at which this error occurred.
+    pub fn phase(&self) -> Phase {
+        match &*self.kind {
+            ErrorKind::Parse { .. }
+            | ErrorKind::FileTooLarge { .. }
+            | ErrorKind::FileInvalidUtf8 { .. } => Phase::Parse,
+            ErrorKind::Check { .. }
+            | ErrorKind::CircularImport { .. }
+            | ErrorKind::InvalidProto3Optional { .. }
+            | ErrorKind::InvalidName { .. }
+            | ErrorKind::TooManyOptions { .. }
+            | ErrorKind::OptionValueTooDeep { .. }
+            | ErrorKind::UnnecessaryAllowAlias { .. }
+            | ErrorKind::DuplicateEnumValueNumberWithoutAlias { .. }
+            | ErrorKind::JsonNameConflict { .. } => Phase::Check,
+            ErrorKind::OpenFile { .. }
+            | ErrorKind::ImportNotFound { .. }
+            | ErrorKind::FileNotIncluded { .. }
+            | ErrorKind::FileShadowed { .. }
+            | ErrorKind::FileConflict { .. }
+            | ErrorKind::SourceConflict { .. }
+            | ErrorKind::TotalInputTooLarge { .. }
+            | ErrorKind::InvalidFileDescriptorSet { .. }
+            | ErrorKind::ListFilesUnsupported
+            | ErrorKind::Custom(_) => Phase::Resolve,
+            #[cfg(feature = "git")]
+            ErrorKind::Git { .. }
+            | ErrorKind::GitNetwork { .. }
+            | ErrorKind::GitAuthentication { .. }
+            | ErrorKind::GitReferenceNotFound { .. } => Phase::Resolve,
+        }
+    }
+
+    /// Returns the source spans highlighted by this error, along with their labels.
+    ///
+    /// This includes the spans of any related errors, such as when multiple errors occur in the same file. The
+    /// spans are byte offsets into the source of the file returned by [`Error::file()`].
+    pub fn spans(&self) -> Vec<(Option<String>, Range<usize>)> {
+        fn add_spans(diagnostic: &dyn Diagnostic, spans: &mut Vec<(Option<String>, Range<usize>)>) {
+            if let Some(labels) = diagnostic.labels() {
+                spans.extend(labels.map(|label| {
+                    (
+                        label.label().map(ToOwned::to_owned),
+                        label.offset()..(label.offset() + label.len()),
+                    )
+                }));
+            }
+            if let Some(related) = diagnostic.related() {
+                for related in related {
+                    add_spans(related, spans);
+                }
+            }
+        }
+
+        let mut spans = Vec::new();
+        add_spans(self, &mut spans);
+        spans
+    }
+
+    /// Returns the messages of this error and of each related error.
+    ///
+    /// When a file contains multiple errors, such as several syntax errors or several undefined type names, they
+    /// are all reported in a single [`struct@Error`]. The first message is that of this error, as returned by its
+    /// `Display` implementation, and is followed by the messages of the related errors in the order they occurred.
+    pub fn messages(&self) -> Vec<String> {
+        let mut messages = vec![self.to_string()];
+        if let Some(related) = self.related() {
+            messages.extend(related.map(|related| related.to_string()));
+        }
+        messages
+    }
+
+    /// Returns the 1-based line and column at which this error occurred, if available.
+    ///
+    /// The location is that of the primary span of the error, in the file returned by [`Error::file()`]. It is
+    /// `None` for errors which are not associated with a location in a source file, such as a missing import.
+    pub fn location(&self) -> Option<(usize, usize)> {
+        fn resolve(source_code: &dyn SourceCode, span: SourceSpan) -> Option<(usize, usize)> {
+            let contents = source_code.read_span(&span, 0, 0).ok()?;
+            Some((contents.line() + 1, contents.column() + 1))
+        }
+
+        match &*self.kind {
+            ErrorKind::Parse { err } => resolve(err.source_code()?, err.span()?.into()),
+            ErrorKind::Check { err } => Some((err.line()? + 1, err.column()? + 1)),
+            ErrorKind::UnnecessaryAllowAlias {
+                span, source_code, ..
+            }
+            | ErrorKind::DuplicateEnumValueNumberWithoutAlias {
+                second: span,
+                source_code,
+                ..
+            }
+            | ErrorKind::JsonNameConflict {
+                second: span,
+                source_code,
+                ..
+            } => resolve(source_code.as_ref()?, (*span)?),
+            _ => None,
+        }
+    }
+
     /// Returns true if this is an instance of [`Error::file_not_found()`]
     pub fn is_file_not_found(&self) -> bool {
         matches!(
@@ -123,6 +352,14 @@ impl Error {
     }
 }
 
+fn fmt_import_chain(imported_by: &[String]) -> String {
+    if imported_by.is_empty() {
+        String::new()
+    } else {
+        format!(" (imported by {})", imported_by.join(" -> "))
+    }
+}
+
 impl From<DescriptorError> for Error {
     fn from(err: DescriptorError) -> Self {
         Error::from_kind(ErrorKind::Check { err })
@@ -147,12 +384,29 @@ impl fmt::Debug for Error {
             ErrorKind::Parse { err } => err.fmt(f),
             ErrorKind::Check { err } => err.fmt(f),
             ErrorKind::OpenFile { err, .. } => write!(f, "{}: {}", self, err),
+            ErrorKind::InvalidFileDescriptorSet { err, .. } => write!(f, "{}: {}", self, err),
             ErrorKind::FileTooLarge { .. }
             | ErrorKind::FileInvalidUtf8 { .. }
             | ErrorKind::ImportNotFound { .. }
             | ErrorKind::CircularImport { .. }
             | ErrorKind::FileNotIncluded { .. }
-            | ErrorKind::FileShadowed { .. } => write!(f, "{}", self),
+            | ErrorKind::FileShadowed { .. }
+            | ErrorKind::FileConflict { .. }
+            | ErrorKind::SourceConflict { .. }
+            | ErrorKind::ListFilesUnsupported
+            | ErrorKind::TotalInputTooLarge { .. }
+            | ErrorKind::InvalidProto3Optional { .. }
+            | ErrorKind::InvalidName { .. }
+            | ErrorKind::TooManyOptions { .. }
+            | ErrorKind::OptionValueTooDeep { .. }
+            | ErrorKind::UnnecessaryAllowAlias { .. }
+            | ErrorKind::DuplicateEnumValueNumberWithoutAlias { .. }
+            | ErrorKind::JsonNameConflict { .. } => write!(f, "{}", self),
+            #[cfg(feature = "git")]
+            ErrorKind::Git { .. }
+            | ErrorKind::GitNetwork { .. }
+            | ErrorKind::GitAuthentication { .. }
+            | ErrorKind::GitReferenceNotFound { .. } => write!(f, "{}", self),
             ErrorKind::Custom(err) => err.fmt(f),
         }
     }
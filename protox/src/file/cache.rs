@@ -0,0 +1,110 @@
+use std::{collections::HashMap, fmt, path::Path, sync::Mutex};
+
+use protox_parse::Syntax;
+
+use super::{File, FileResolver};
+use crate::Error;
+
+/// An implementation of [`FileResolver`] which caches files opened by another resolver.
+///
+/// Before re-opening a file, the cache consults [`FileResolver::file_version`] on the inner resolver. If it
+/// returns the same version as when the file was last opened, the cached file is returned without re-parsing it.
+/// Files for which the inner resolver reports no version are never cached. Files opened with a different
+/// default syntax are parsed again.
+pub struct CachingFileResolver<R> {
+    inner: R,
+    cache: Mutex<HashMap<String, CachedFile>>,
+}
+
+struct CachedFile {
+    version: u64,
+    default_syntax: Option<Syntax>,
+    file: File,
+}
+
+impl<R> CachingFileResolver<R>
+where
+    R: FileResolver,
+{
+    /// Creates a new [`CachingFileResolver`] wrapping the given resolver.
+    pub fn new(inner: R) -> Self {
+        CachingFileResolver {
+            inner,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Gets a reference to the wrapped resolver.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Removes all cached files.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+}
+
+impl<R> FileResolver for CachingFileResolver<R>
+where
+    R: FileResolver,
+{
+    fn resolve_path(&self, path: &Path) -> Option<String> {
+        self.inner.resolve_path(path)
+    }
+
+    fn open_file(&self, name: &str) -> Result<File, Error> {
+        self.open_file_with_syntax(name, Some(Syntax::Proto2))
+    }
+
+    fn open_file_with_syntax(
+        &self,
+        name: &str,
+        default_syntax: Option<Syntax>,
+    ) -> Result<File, Error> {
+        let version = match self.inner.file_version(name) {
+            Some(version) => version,
+            None => return self.inner.open_file_with_syntax(name, default_syntax),
+        };
+
+        if let Some(cached) = self.cache.lock().unwrap().get(name) {
+            if cached.version == version && cached.default_syntax == default_syntax {
+                return Ok(cached.file.clone());
+            }
+        }
+
+        let file = self.inner.open_file_with_syntax(name, default_syntax)?;
+        self.cache.lock().unwrap().insert(
+            name.to_owned(),
+            CachedFile {
+                version,
+                default_syntax,
+                file: file.clone(),
+            },
+        );
+        Ok(file)
+    }
+
+    fn contains_file(&self, name: &str) -> bool {
+        self.inner.contains_file(name)
+    }
+
+    fn file_version(&self, name: &str) -> Option<u64> {
+        self.inner.file_version(name)
+    }
+
+    fn list_files(&self) -> Option<Vec<String>> {
+        self.inner.list_files()
+    }
+}
+
+impl<R> fmt::Debug for CachingFileResolver<R>
+where
+    R: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachingFileResolver")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
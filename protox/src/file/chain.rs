@@ -1,14 +1,30 @@
-use std::{fmt, path::Path};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    fmt,
+    hash::{Hash, Hasher},
+    path::Path,
+    sync::Mutex,
+};
+
+use protox_parse::Syntax;
 
 use super::{File, FileResolver};
-use crate::Error;
+use crate::{error::ErrorKind, Error};
 
 /// An implementation of [`FileResolver`] which chains together several other resolvers.
 ///
 /// When opening files, each resolver is searched in turn until the file is found.
+///
+/// Paths are resolved to names and names are opened independently. The name for a path is given by the
+/// first resolver which can resolve it, while the file for a name is given by the first resolver which can
+/// open it, even if that is a different resolver. When compiling a file by path, the
+/// [`Compiler`](crate::Compiler) checks that the opened file matches the given path, and returns an error
+/// if it was shadowed by a file in another resolver.
 #[derive(Default)]
 pub struct ChainFileResolver {
     resolvers: Vec<Box<dyn FileResolver>>,
+    strict_uniqueness: bool,
+    opened_by: Mutex<HashMap<String, usize>>,
 }
 
 impl ChainFileResolver {
@@ -26,6 +42,25 @@ impl ChainFileResolver {
     {
         self.resolvers.push(Box::new(resolver))
     }
+
+    /// Sets whether an error is returned when more than one resolver can open a file with different contents.
+    ///
+    /// By default, the first resolver which can open a file is used, and any others are ignored. If this option
+    /// is set, every resolver is searched when opening a file, which can catch accidental shadowing between
+    /// different include paths.
+    pub fn strict_uniqueness(&mut self, yes: bool) -> &mut Self {
+        self.strict_uniqueness = yes;
+        self
+    }
+
+    /// Gets the index of the resolver which most recently opened the file with the given name.
+    ///
+    /// Resolvers are numbered from zero in the order they were [added](ChainFileResolver::add). Returns `None` if
+    /// no file with this name has been opened successfully. This can help to diagnose which include path a file
+    /// was loaded from when several of them contain files with the same name.
+    pub fn resolver_index(&self, name: &str) -> Option<usize> {
+        self.opened_by.lock().unwrap().get(name).copied()
+    }
 }
 
 impl FileResolver for ChainFileResolver {
@@ -40,20 +75,105 @@ impl FileResolver for ChainFileResolver {
     }
 
     fn open_file(&self, name: &str) -> Result<File, Error> {
-        for resolver in &self.resolvers {
-            match resolver.open_file(name) {
-                Ok(file) => return Ok(file),
+        self.open_file_with_syntax(name, Some(Syntax::Proto2))
+    }
+
+    fn open_file_with_syntax(
+        &self,
+        name: &str,
+        default_syntax: Option<Syntax>,
+    ) -> Result<File, Error> {
+        let mut found: Option<(usize, File)> = None;
+        for (index, resolver) in self.resolvers.iter().enumerate() {
+            match resolver.open_file_with_syntax(name, default_syntax) {
+                Ok(file) => match &found {
+                    None if !self.strict_uniqueness => {
+                        found = Some((index, file));
+                        break;
+                    }
+                    None => found = Some((index, file)),
+                    Some((_, first)) if !same_contents(first, &file) => {
+                        return Err(Error::from_kind(ErrorKind::FileConflict {
+                            name: name.to_owned(),
+                        }))
+                    }
+                    Some(_) => continue,
+                },
                 Err(err) if err.is_file_not_found() => continue,
                 Err(err) => return Err(err),
             }
         }
 
-        Err(Error::file_not_found(name))
+        match found {
+            Some((index, file)) => {
+                self.opened_by
+                    .lock()
+                    .unwrap()
+                    .insert(name.to_owned(), index);
+                Ok(file)
+            }
+            None => Err(Error::file_not_found(name)),
+        }
+    }
+
+    /// Returns `true` if any resolver in the chain has the file.
+    fn contains_file(&self, name: &str) -> bool {
+        self.resolvers
+            .iter()
+            .any(|resolver| resolver.contains_file(name))
+    }
+
+    /// Gets the version of a file from the first resolver in the chain which has it.
+    ///
+    /// A resolver has the file if it reports a version for it, or if [`contains_file`](FileResolver::contains_file)
+    /// returns `true`. If that resolver does not report a version, `None` is returned, since the file it opens
+    /// cannot be versioned. The version also depends on the position of that resolver, so it changes if the file
+    /// is later found by a different resolver.
+    fn file_version(&self, name: &str) -> Option<u64> {
+        for (index, resolver) in self.resolvers.iter().enumerate() {
+            if let Some(version) = resolver.file_version(name) {
+                let mut hasher = DefaultHasher::new();
+                (index, version).hash(&mut hasher);
+                return Some(hasher.finish());
+            }
+            if resolver.contains_file(name) {
+                return None;
+            }
+        }
+
+        None
+    }
+
+    /// Lists the files of every resolver in the chain which can list its files.
+    ///
+    /// Names are returned in the order of the resolvers, and a name provided by more than one resolver is only
+    /// listed once. Returns `None` if none of the resolvers can list their files.
+    fn list_files(&self) -> Option<Vec<String>> {
+        let mut files: Option<Vec<String>> = None;
+        let mut seen = HashSet::new();
+        for names in self
+            .resolvers
+            .iter()
+            .filter_map(|resolver| resolver.list_files())
+        {
+            let files = files.get_or_insert_with(Vec::new);
+            files.extend(names.into_iter().filter(|name| seen.insert(name.clone())));
+        }
+        files
     }
 }
 
 impl fmt::Debug for ChainFileResolver {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("ChainFileResolver").finish_non_exhaustive()
+        f.debug_struct("ChainFileResolver")
+            .field("strict_uniqueness", &self.strict_uniqueness)
+            .finish_non_exhaustive()
+    }
+}
+
+fn same_contents(l: &File, r: &File) -> bool {
+    match (l.source(), r.source()) {
+        (Some(l), Some(r)) => l == r,
+        _ => l.file_descriptor_proto() == r.file_descriptor_proto(),
     }
 }
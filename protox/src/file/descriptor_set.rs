@@ -1,3 +1,5 @@
+use std::{fs, path::Path};
+
 use bytes::{Buf, Bytes};
 use prost::{
     encoding::{check_wire_type, decode_key, decode_varint, skip_field, DecodeContext, WireType},
@@ -6,6 +8,7 @@ use prost::{
 use prost_types::FileDescriptorProto;
 
 use crate::{
+    error::ErrorKind,
     file::{File, FileResolver},
     Error,
 };
@@ -63,6 +66,32 @@ impl DescriptorSetFileResolver {
         }
         Ok(DescriptorSetFileResolver { set })
     }
+
+    /// Creates an instance of [`DescriptorSetFileResolver`] by reading a [`FileDescriptorSet`](prost_types::FileDescriptorSet)
+    /// from a file, such as one written by `protoc --descriptor_set_out`.
+    ///
+    /// As with [`decode()`](DescriptorSetFileResolver::decode), extension options are preserved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, or does not contain a valid file descriptor set.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|err| {
+            Error::from_kind(ErrorKind::OpenFile {
+                name: path.display().to_string(),
+                path: path.to_owned(),
+                err,
+            })
+        })?;
+
+        DescriptorSetFileResolver::decode(bytes.as_slice()).map_err(|err| {
+            Error::from_kind(ErrorKind::InvalidFileDescriptorSet {
+                path: path.to_owned(),
+                err,
+            })
+        })
+    }
 }
 
 impl FileResolver for DescriptorSetFileResolver {
@@ -80,6 +109,20 @@ impl FileResolver for DescriptorSetFileResolver {
 
         Err(Error::file_not_found(name))
     }
+
+    fn contains_file(&self, name: &str) -> bool {
+        self.set.iter().any(|file| file.file.name() == name)
+    }
+
+    /// Lists the names of all files in the set, in the order they appear.
+    fn list_files(&self) -> Option<Vec<String>> {
+        Some(
+            self.set
+                .iter()
+                .map(|file| file.file.name().to_owned())
+                .collect(),
+        )
+    }
 }
 
 impl FileDescriptor {
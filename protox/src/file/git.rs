@@ -0,0 +1,227 @@
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use git2::{build::RepoBuilder, ErrorClass, ErrorCode, ObjectType, Oid, Repository, TreeWalkMode};
+use protox_parse::Syntax;
+
+use super::{File, FileResolver, MAX_FILE_LEN};
+use crate::{error::ErrorKind, Error};
+
+/// An implementation of [`FileResolver`] which reads files from a commit in a git repository.
+///
+/// Files are read directly from the git object database using [`git2`], so they do not need to be checked out.
+/// The tree for the given reference is resolved once when the resolver is created, so later changes to the
+/// reference do not affect the files it returns.
+///
+/// A local repository can be opened with [`new`](GitFileResolver::new), and a remote repository can be cloned
+/// with [`from_url`](GitFileResolver::from_url). Only local and unencrypted transports are supported by default:
+/// to clone over HTTPS or SSH, enable the `https` or `ssh` features of the `git2` crate.
+pub struct GitFileResolver {
+    repository: Mutex<Repository>,
+    files: HashMap<String, Oid>,
+}
+
+impl GitFileResolver {
+    /// Creates a new [`GitFileResolver`] serving files under `subpath` in the tree of `reference` in a local
+    /// repository.
+    ///
+    /// The repository may be bare. The reference may be anything accepted by `git rev-parse`, such as a branch,
+    /// tag or commit hash. File names are relative to `subpath`, which may be empty to serve files relative to the
+    /// repository root.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the repository cannot be opened, or if the reference or subpath does not exist.
+    pub fn new(
+        repository: impl AsRef<Path>,
+        reference: &str,
+        subpath: impl AsRef<Path>,
+    ) -> Result<Self, Error> {
+        let repository = Repository::open(repository.as_ref()).map_err(git_error)?;
+        GitFileResolver::with_repository(repository, reference, subpath.as_ref())
+    }
+
+    /// Clones the repository at `url` into `directory`, and creates a new [`GitFileResolver`] serving files under
+    /// `subpath` in the tree of `reference`.
+    ///
+    /// The repository is cloned as a bare repository, and `directory` must either not exist or be empty. Once
+    /// cloned, the same directory can be opened again with [`new`](GitFileResolver::new) without fetching it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the repository cannot be cloned, for example due to a network or authentication
+    /// failure, or if the reference or subpath does not exist.
+    pub fn from_url(
+        url: &str,
+        directory: impl AsRef<Path>,
+        reference: &str,
+        subpath: impl AsRef<Path>,
+    ) -> Result<Self, Error> {
+        // Create the directory first, so that errors from the clone itself come from fetching the repository.
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory).map_err(|err| {
+            Error::from_kind(ErrorKind::OpenFile {
+                name: url.to_owned(),
+                path: directory.to_owned(),
+                err,
+            })
+        })?;
+        let repository = RepoBuilder::new()
+            .bare(true)
+            .clone(url, directory)
+            .map_err(|err| clone_error(url, err))?;
+        GitFileResolver::with_repository(repository, reference, subpath.as_ref())
+    }
+
+    fn with_repository(
+        repository: Repository,
+        reference: &str,
+        subpath: &Path,
+    ) -> Result<Self, Error> {
+        let subpath = subpath.to_string_lossy().replace('\\', "/");
+        let subpath = subpath.trim_matches('/');
+
+        let revision_error = |err: git2::Error| match err.code() {
+            ErrorCode::NotFound | ErrorCode::InvalidSpec => {
+                Error::from_kind(ErrorKind::GitReferenceNotFound {
+                    reference: reference.to_owned(),
+                })
+            }
+            _ => git_error(err),
+        };
+
+        let mut tree = repository
+            .revparse_single(reference)
+            .and_then(|object| object.peel_to_tree())
+            .map_err(revision_error)?;
+        if !subpath.is_empty() {
+            tree = tree
+                .get_path(Path::new(subpath))
+                .and_then(|entry| entry.to_object(&repository))
+                .and_then(|object| object.peel_to_tree())
+                .map_err(git_error)?;
+        }
+
+        let mut files = HashMap::new();
+        tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.kind() == Some(ObjectType::Blob) {
+                if let Some(name) = entry.name() {
+                    files.insert(format!("{}{}", dir, name), entry.id());
+                }
+            }
+            git2::TreeWalkResult::Ok
+        })
+        .map_err(git_error)?;
+        drop(tree);
+
+        Ok(GitFileResolver {
+            repository: Mutex::new(repository),
+            files,
+        })
+    }
+}
+
+impl FileResolver for GitFileResolver {
+    /// Opens a file by its unique name.
+    ///
+    /// # Errors
+    ///
+    /// If the tree does not contain the file, [`Error::file_not_found()`] is returned.
+    fn open_file(&self, name: &str) -> Result<File, Error> {
+        self.open_file_with_syntax(name, Some(Syntax::Proto2))
+    }
+
+    fn open_file_with_syntax(
+        &self,
+        name: &str,
+        default_syntax: Option<Syntax>,
+    ) -> Result<File, Error> {
+        let id = match self.files.get(name) {
+            Some(id) => *id,
+            None => return Err(Error::file_not_found(name)),
+        };
+
+        let source = {
+            let repository = self.repository.lock().unwrap();
+            let blob = repository.find_blob(id).map_err(git_error)?;
+            if blob.size() as u64 > MAX_FILE_LEN {
+                return Err(Error::from_kind(ErrorKind::FileTooLarge {
+                    name: name.to_owned(),
+                }));
+            }
+
+            String::from_utf8(blob.content().to_vec()).map_err(|_| {
+                Error::from_kind(ErrorKind::FileInvalidUtf8 {
+                    name: name.to_owned(),
+                })
+            })?
+        };
+
+        File::from_source_with_syntax(name, &source, default_syntax)
+    }
+
+    fn contains_file(&self, name: &str) -> bool {
+        self.files.contains_key(name)
+    }
+
+    /// Gets the version of a file, derived from its git object id.
+    fn file_version(&self, name: &str) -> Option<u64> {
+        let id = self.files.get(name)?;
+        Some(u64::from_be_bytes(id.as_bytes().get(..8)?.try_into().ok()?))
+    }
+
+    /// Lists the names of all `.proto` files in the tree, in sorted order.
+    fn list_files(&self) -> Option<Vec<String>> {
+        let mut names: Vec<String> = self
+            .files
+            .keys()
+            .filter(|name| name.ends_with(".proto"))
+            .cloned()
+            .collect();
+        names.sort();
+        Some(names)
+    }
+}
+
+impl fmt::Debug for GitFileResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path: Option<PathBuf> = self
+            .repository
+            .lock()
+            .ok()
+            .map(|repository| repository.path().to_owned());
+        f.debug_struct("GitFileResolver")
+            .field("repository", &path)
+            .finish_non_exhaustive()
+    }
+}
+
+fn clone_error(url: &str, err: git2::Error) -> Error {
+    let message = err.message().to_owned();
+    match (err.code(), err.class()) {
+        (ErrorCode::Auth | ErrorCode::Certificate, _) => {
+            Error::from_kind(ErrorKind::GitAuthentication {
+                url: url.to_owned(),
+                message,
+            })
+        }
+        (
+            _,
+            ErrorClass::Net | ErrorClass::Http | ErrorClass::Ssl | ErrorClass::Ssh | ErrorClass::Os,
+        ) => Error::from_kind(ErrorKind::GitNetwork {
+            url: url.to_owned(),
+            message,
+        }),
+        _ => git_error(err),
+    }
+}
+
+fn git_error(err: git2::Error) -> Error {
+    Error::from_kind(ErrorKind::Git {
+        message: err.message().to_owned(),
+    })
+}
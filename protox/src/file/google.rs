@@ -39,22 +39,36 @@ pub(crate) const COMPILER_PLUGIN: &str = include_proto!("compiler/plugin.proto")
 
 impl FileResolver for GoogleFileResolver {
     fn open_file(&self, name: &str) -> Result<File, Error> {
-        let source = match name {
-            "google/protobuf/any.proto" => ANY,
-            "google/protobuf/api.proto" => API,
-            "google/protobuf/descriptor.proto" => DESCRIPTOR,
-            "google/protobuf/duration.proto" => DURATION,
-            "google/protobuf/empty.proto" => EMPTY,
-            "google/protobuf/field_mask.proto" => FIELD_MASK,
-            "google/protobuf/source_context.proto" => SOURCE_CONTEXT,
-            "google/protobuf/struct.proto" => STRUCT,
-            "google/protobuf/timestamp.proto" => TIMESTAMP,
-            "google/protobuf/type.proto" => TYPE,
-            "google/protobuf/wrappers.proto" => WRAPPERS,
-            "google/protobuf/compiler/plugin.proto" => COMPILER_PLUGIN,
-            _ => return Err(Error::file_not_found(name)),
-        };
-
-        File::from_source(name, source)
+        match source(name) {
+            Some(source) => File::from_source(name, source),
+            None => Err(Error::file_not_found(name)),
+        }
     }
+
+    fn contains_file(&self, name: &str) -> bool {
+        source(name).is_some()
+    }
+
+    /// Gets the version of a bundled file, which never changes.
+    fn file_version(&self, name: &str) -> Option<u64> {
+        source(name).map(|_| 0)
+    }
+}
+
+fn source(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "google/protobuf/any.proto" => ANY,
+        "google/protobuf/api.proto" => API,
+        "google/protobuf/descriptor.proto" => DESCRIPTOR,
+        "google/protobuf/duration.proto" => DURATION,
+        "google/protobuf/empty.proto" => EMPTY,
+        "google/protobuf/field_mask.proto" => FIELD_MASK,
+        "google/protobuf/source_context.proto" => SOURCE_CONTEXT,
+        "google/protobuf/struct.proto" => STRUCT,
+        "google/protobuf/timestamp.proto" => TIMESTAMP,
+        "google/protobuf/type.proto" => TYPE,
+        "google/protobuf/wrappers.proto" => WRAPPERS,
+        "google/protobuf/compiler/plugin.proto" => COMPILER_PLUGIN,
+        _ => return None,
+    })
 }
@@ -0,0 +1,132 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read},
+    sync::Mutex,
+};
+
+use protox_parse::Syntax;
+use reqwest::{blocking::Client, StatusCode, Url};
+
+use super::{File, FileResolver, MAX_FILE_LEN};
+use crate::{error::ErrorKind, Error};
+
+/// An implementation of [`FileResolver`] which fetches files over HTTP, such as from a schema registry.
+///
+/// Each file is requested from the URL formed by joining its name onto a base URL, using a blocking
+/// [`reqwest`] client. Fetched files are cached, so each file is only requested once by a given resolver.
+///
+/// Only plain HTTP is supported by default: to fetch files over HTTPS, enable one of the TLS features of the
+/// `reqwest` crate, such as `rustls-tls`.
+#[derive(Debug)]
+pub struct HttpFileResolver {
+    base_url: String,
+    client: Client,
+    cache: Mutex<HashMap<String, String>>,
+}
+
+impl HttpFileResolver {
+    /// Creates a new [`HttpFileResolver`] which fetches files relative to `base_url`.
+    ///
+    /// For example, with a base URL of `http://example.com/protos`, the import `foo/bar.proto` is fetched
+    /// from `http://example.com/protos/foo/bar.proto`. An `https://` base URL requires one of the TLS features of
+    /// the `reqwest` crate to be enabled, as described [above](HttpFileResolver).
+    pub fn new(base_url: impl Into<String>) -> Self {
+        HttpFileResolver {
+            base_url: base_url.into(),
+            client: Client::new(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn fetch(&self, name: &str) -> Result<String, Error> {
+        let mut url = Url::parse(&self.base_url).map_err(io_error)?;
+        url.path_segments_mut()
+            .map_err(|()| io_error(format!("'{}' cannot be used as a base URL", self.base_url)))?
+            .pop_if_empty()
+            .extend(name.split('/'));
+
+        let response = self.client.get(url.clone()).send().map_err(io_error)?;
+        match response.status() {
+            StatusCode::OK => (),
+            StatusCode::NOT_FOUND => return Err(Error::file_not_found(name)),
+            status => {
+                return Err(io_error(format!(
+                    "request to '{}' failed with status {}",
+                    url,
+                    status.as_u16()
+                )))
+            }
+        }
+
+        let too_large = || {
+            Error::from_kind(ErrorKind::FileTooLarge {
+                name: name.to_owned(),
+            })
+        };
+        if response
+            .content_length()
+            .is_some_and(|len| len > MAX_FILE_LEN)
+        {
+            return Err(too_large());
+        }
+
+        let mut body = Vec::new();
+        response
+            .take(MAX_FILE_LEN + 1)
+            .read_to_end(&mut body)
+            .map_err(io_error)?;
+        if body.len() as u64 > MAX_FILE_LEN {
+            return Err(too_large());
+        }
+
+        String::from_utf8(body).map_err(|_| {
+            Error::from_kind(ErrorKind::FileInvalidUtf8 {
+                name: name.to_owned(),
+            })
+        })
+    }
+}
+
+impl FileResolver for HttpFileResolver {
+    /// Fetches a file by its unique name.
+    ///
+    /// # Errors
+    ///
+    /// If the server responds with status 404, [`Error::file_not_found()`] is returned. Other unsuccessful
+    /// responses, and failures to make the request, are returned as IO errors.
+    fn open_file(&self, name: &str) -> Result<File, Error> {
+        self.open_file_with_syntax(name, Some(Syntax::Proto2))
+    }
+
+    fn open_file_with_syntax(
+        &self,
+        name: &str,
+        default_syntax: Option<Syntax>,
+    ) -> Result<File, Error> {
+        let cached = self.cache.lock().unwrap().get(name).cloned();
+        let source = match cached {
+            Some(source) => source,
+            None => {
+                let source = self.fetch(name)?;
+                self.cache
+                    .lock()
+                    .unwrap()
+                    .insert(name.to_owned(), source.clone());
+                source
+            }
+        };
+
+        File::from_source_with_syntax(name, &source, default_syntax)
+    }
+
+    /// Returns `true` if the file has already been fetched.
+    ///
+    /// Other files are not requested, so this may return `false` for a file the server does have.
+    fn contains_file(&self, name: &str) -> bool {
+        self.cache.lock().unwrap().contains_key(name)
+    }
+}
+
+fn io_error(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Error {
+    Error::new(io::Error::new(io::ErrorKind::Other, err))
+}
@@ -1,25 +1,67 @@
-use std::path::{self, Path, PathBuf};
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    fs,
+    hash::{Hash, Hasher},
+    path::{self, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use protox_parse::Syntax;
 
 use crate::{error::ErrorKind, Error};
 
 use super::{File, FileResolver};
 
-/// An implementation of [`FileResolver`] which searches an include path on the file system.
+/// An implementation of [`FileResolver`] which searches one or more include paths on the file system.
+///
+/// As with protoc's `--proto_path` flag, include paths are searched in order, and a file under an earlier
+/// include path shadows a file with the same name under a later one. When compiling a file by path, the
+/// [`Compiler`](crate::Compiler) returns an error if the file was shadowed in this way.
 #[derive(Debug)]
 pub struct IncludeFileResolver {
-    include: PathBuf,
+    includes: Vec<PathBuf>,
 }
 
 impl IncludeFileResolver {
     /// Constructs a `IncludeFileResolver` that searches the given include path.
     pub fn new(include: PathBuf) -> Self {
-        IncludeFileResolver { include }
+        IncludeFileResolver {
+            includes: vec![include],
+        }
+    }
+
+    /// Constructs a `IncludeFileResolver` that searches each of the given include paths in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::path::{Path, PathBuf};
+    /// # use protox::file::{IncludeFileResolver, FileResolver};
+    /// let resolver = IncludeFileResolver::with_includes(["/path/to/include", "/path/to/other"]);
+    /// assert_eq!(resolver.include_paths(), [PathBuf::from("/path/to/include"), PathBuf::from("/path/to/other")]);
+    /// assert_eq!(resolver.resolve_path(Path::new("/path/to/other/foo.proto")), Some("foo.proto".to_owned()));
+    /// ```
+    pub fn with_includes<I, P>(includes: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        IncludeFileResolver {
+            includes: includes.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Gets the include paths searched by this resolver, in order.
+    pub fn include_paths(&self) -> &[PathBuf] {
+        &self.includes
     }
 }
 
 impl FileResolver for IncludeFileResolver {
     /// Converts a file system path to a unique file name.
     ///
+    /// The name is relative to the first include path which contains `path`.
+    ///
     /// # Examples
     ///
     /// ```
@@ -30,9 +72,11 @@ impl FileResolver for IncludeFileResolver {
     /// assert_eq!(resolver.resolve_path(Path::new("notincluded.proto")), None);
     /// ```
     fn resolve_path(&self, path: &Path) -> Option<String> {
-        if let Some(relative_path) = strip_prefix(path, &self.include) {
-            if let Some(name) = path_to_file_name(relative_path) {
-                return Some(name);
+        for include in &self.includes {
+            if let Some(relative_path) = strip_prefix(path, include) {
+                if let Some(name) = path_to_file_name(relative_path) {
+                    return Some(name);
+                }
             }
         }
 
@@ -41,7 +85,7 @@ impl FileResolver for IncludeFileResolver {
 
     /// Opens a file by its unique name.
     ///
-    /// If the include path contains a file with the given name, it is parsed and returned.
+    /// The file with the given name under the first include path which contains it is parsed and returned.
     ///
     /// # Errors
     ///
@@ -65,7 +109,87 @@ impl FileResolver for IncludeFileResolver {
     /// assert_eq!(file.source(), Some("/* hello! */"));
     /// ```
     fn open_file(&self, name: &str) -> Result<File, Error> {
-        File::open(name, &self.include.join(name))
+        self.open_file_with_syntax(name, Some(Syntax::Proto2))
+    }
+
+    fn open_file_with_syntax(
+        &self,
+        name: &str,
+        default_syntax: Option<Syntax>,
+    ) -> Result<File, Error> {
+        for include in &self.includes {
+            match File::open_with_syntax(name, &include.join(name), default_syntax) {
+                Err(err) if err.is_file_not_found() => continue,
+                result => return result,
+            }
+        }
+
+        Err(Error::file_not_found(name))
+    }
+
+    /// Returns `true` if the file exists under any of the include paths.
+    fn contains_file(&self, name: &str) -> bool {
+        self.includes
+            .iter()
+            .any(|include| include.join(name).is_file())
+    }
+
+    /// Gets the version of a file, derived from its modification time and length.
+    ///
+    /// The version also depends on which include path contains the file, so it changes if the file becomes
+    /// shadowed by a file under an earlier include path. Returns `None` if the file does not exist, or its
+    /// modification time is not available.
+    fn file_version(&self, name: &str) -> Option<u64> {
+        for (index, include) in self.includes.iter().enumerate() {
+            let metadata = match fs::metadata(include.join(name)) {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+            let mut hasher = DefaultHasher::new();
+            (index, metadata.len(), modified).hash(&mut hasher);
+            return Some(hasher.finish());
+        }
+
+        None
+    }
+
+    /// Lists the names of all `.proto` files under the include paths.
+    ///
+    /// Each include path is searched recursively, and the names under it are sorted. A name found under more
+    /// than one include path is only listed once, since the later files are shadowed. Directories which cannot
+    /// be read are skipped.
+    fn list_files(&self) -> Option<Vec<String>> {
+        let mut files = Vec::new();
+        let mut seen = HashSet::new();
+        for include in &self.includes {
+            let mut names = Vec::new();
+            list_proto_files(include, Path::new(""), &mut names);
+            names.sort();
+            files.extend(names.into_iter().filter(|name| seen.insert(name.clone())));
+        }
+        Some(files)
+    }
+}
+
+fn list_proto_files(include: &Path, dir: &Path, names: &mut Vec<String>) {
+    let entries = match fs::read_dir(include.join(dir)) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = dir.join(entry.file_name());
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => list_proto_files(include, &path, names),
+            Ok(_) if path.extension().is_some_and(|ext| ext == "proto") => {
+                if let Some(name) = path_to_file_name(&path) {
+                    names.push(name);
+                }
+            }
+            _ => (),
+        }
     }
 }
 
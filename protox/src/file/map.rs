@@ -0,0 +1,75 @@
+use std::{collections::HashMap, path::Path};
+
+use protox_parse::Syntax;
+
+use super::{path_to_file_name, File, FileResolver};
+use crate::Error;
+
+/// An implementation of [`FileResolver`] which serves files from an in-memory map of file names to source code.
+///
+/// This allows files to be compiled without accessing the file system, for example when they are embedded in
+/// a binary or when targeting WebAssembly.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use protox::{Compiler, file::MapFileResolver};
+/// let resolver = MapFileResolver::new(HashMap::from([
+///     ("dep.proto".to_owned(), "message Dep {}".to_owned()),
+///     ("root.proto".to_owned(), "import 'dep.proto'; message Root { optional Dep dep = 1; }".to_owned()),
+/// ]));
+///
+/// let mut compiler = Compiler::with_file_resolver(resolver);
+/// compiler.open_file("root.proto").unwrap();
+/// assert_eq!(compiler.files().count(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MapFileResolver {
+    files: HashMap<String, String>,
+}
+
+impl MapFileResolver {
+    /// Creates a new [`MapFileResolver`] serving the given files, keyed by their unique name.
+    pub fn new(files: HashMap<String, String>) -> Self {
+        MapFileResolver { files }
+    }
+}
+
+impl FileResolver for MapFileResolver {
+    /// Converts a path to a file name, if it matches the name of one of the files in the map.
+    fn resolve_path(&self, path: &Path) -> Option<String> {
+        path_to_file_name(path).filter(|name| self.files.contains_key(name))
+    }
+
+    /// Opens a file by its unique name.
+    ///
+    /// # Errors
+    ///
+    /// If the map does not contain the file, [`Error::file_not_found()`] is returned.
+    fn open_file(&self, name: &str) -> Result<File, Error> {
+        self.open_file_with_syntax(name, Some(Syntax::Proto2))
+    }
+
+    fn open_file_with_syntax(
+        &self,
+        name: &str,
+        default_syntax: Option<Syntax>,
+    ) -> Result<File, Error> {
+        match self.files.get(name) {
+            Some(source) => File::from_source_with_syntax(name, source, default_syntax),
+            None => Err(Error::file_not_found(name)),
+        }
+    }
+
+    fn contains_file(&self, name: &str) -> bool {
+        self.files.contains_key(name)
+    }
+
+    /// Lists the names of all files in the map, in sorted order.
+    fn list_files(&self) -> Option<Vec<String>> {
+        let mut names: Vec<String> = self.files.keys().cloned().collect();
+        names.sort();
+        Some(names)
+    }
+}
@@ -1,29 +1,46 @@
 //! Interfaces for customizing resolution of protobuf source files.
 
+mod cache;
 mod chain;
 mod descriptor_set;
+#[cfg(feature = "git")]
+mod git;
 mod google;
+#[cfg(feature = "reqwest")]
+mod http;
 mod include;
+mod map;
 #[cfg(test)]
 mod tests;
 
+pub use cache::CachingFileResolver;
 pub use chain::ChainFileResolver;
 pub use descriptor_set::DescriptorSetFileResolver;
+#[cfg(feature = "git")]
+pub use git::GitFileResolver;
 pub use google::GoogleFileResolver;
+#[cfg(feature = "reqwest")]
+pub use http::HttpFileResolver;
 pub use include::IncludeFileResolver;
+pub use map::MapFileResolver;
 use prost_types::FileDescriptorProto;
 
 use std::{
     fs,
     io::{self, Read},
+    ops::Range,
     path::{Path, PathBuf},
 };
 
 use bytes::{Buf, Bytes};
 pub(crate) use include::{check_shadow, path_to_file_name};
 use prost::{DecodeError, Message};
+use protox_parse::Syntax;
 
-use crate::error::{Error, ErrorKind};
+use crate::{
+    error::{Error, ErrorKind},
+    Warning,
+};
 
 const MAX_FILE_LEN: u64 = i32::MAX as u64;
 
@@ -43,6 +60,65 @@ pub trait FileResolver {
     ///
     /// If the file is not found, the implementation should return [`Error::file_not_found`].
     fn open_file(&self, name: &str) -> Result<File, Error>;
+
+    /// Opens a file by its unique name, using `default_syntax` for source files without a `syntax` statement.
+    ///
+    /// This is called by the [`Compiler`](crate::Compiler) instead of [`open_file`](FileResolver::open_file),
+    /// so that [`Compiler::default_syntax`](crate::Compiler::default_syntax) applies to the files it loads. If
+    /// `default_syntax` is `None`, files without a `syntax` statement should be rejected.
+    ///
+    /// The default implementation calls [`open_file`](FileResolver::open_file), and parses the source of the
+    /// returned file again if it does not have a `syntax` statement. Resolvers which parse source files should
+    /// override it to use [`File::open_with_syntax`] or [`File::from_source_with_syntax`] directly, since a file
+    /// written for another syntax may fail to parse as `proto2`.
+    fn open_file_with_syntax(
+        &self,
+        name: &str,
+        default_syntax: Option<Syntax>,
+    ) -> Result<File, Error> {
+        self.open_file(name)?.reparse_with_syntax(default_syntax)
+    }
+
+    /// Returns `true` if this resolver has a file with the given name, without opening it.
+    ///
+    /// This is used by [`Compiler::add_source`](crate::Compiler::add_source) to detect files which conflict with a
+    /// file added from source. It should be cheap, and should not parse the file. The default implementation returns
+    /// `false`, meaning such conflicts are not detected.
+    fn contains_file(&self, _name: &str) -> bool {
+        false
+    }
+
+    /// Gets the current version of a file, such as its modification time or a hash of its contents.
+    ///
+    /// This is used by [`CachingFileResolver`] to decide whether a previously opened file can be reused
+    /// without parsing it again. If the file has not changed, the implementation should return the same
+    /// version as the last time it was opened.
+    ///
+    /// The default implementation returns `None`, meaning the file is always re-opened.
+    fn file_version(&self, _name: &str) -> Option<u64> {
+        None
+    }
+
+    /// Lists the names of all files which can be opened by this resolver.
+    ///
+    /// This is used by [`Compiler::add_all_files`](crate::Compiler::add_all_files) to compile every file a
+    /// resolver knows about, without listing them explicitly. The default implementation returns `None`, meaning
+    /// the resolver cannot enumerate its files.
+    fn list_files(&self) -> Option<Vec<String>> {
+        None
+    }
+}
+
+/// A strategy for locating the file which defines a given symbol.
+///
+/// This can be used with [`Compiler::symbol_resolver`](crate::Compiler::symbol_resolver) to load files
+/// which define referenced types but are not explicitly imported, for example when fetching dependencies
+/// from a schema registry.
+pub trait SymbolResolver {
+    /// Gets the name of the file which defines the given fully-qualified symbol, such as `foo.Bar`.
+    ///
+    /// The returned name is opened with the [`FileResolver`] in the same way as an `import` statement.
+    fn resolve_symbol(&self, name: &str) -> Option<String>;
 }
 
 impl<T> FileResolver for Box<T>
@@ -56,6 +132,26 @@ where
     fn open_file(&self, name: &str) -> Result<File, Error> {
         (**self).open_file(name)
     }
+
+    fn open_file_with_syntax(
+        &self,
+        name: &str,
+        default_syntax: Option<Syntax>,
+    ) -> Result<File, Error> {
+        (**self).open_file_with_syntax(name, default_syntax)
+    }
+
+    fn contains_file(&self, name: &str) -> bool {
+        (**self).contains_file(name)
+    }
+
+    fn file_version(&self, name: &str) -> Option<u64> {
+        (**self).file_version(name)
+    }
+
+    fn list_files(&self) -> Option<Vec<String>> {
+        (**self).list_files()
+    }
 }
 
 /// An opened protobuf source file, returned by [`FileResolver::open_file`].
@@ -73,6 +169,12 @@ pub struct FileMetadata {
     pub(crate) name: String,
     pub(crate) path: Option<PathBuf>,
     pub(crate) is_import: bool,
+    /// The number of dependencies added to the end of the descriptor in the pool, which are not part of the file.
+    pub(crate) hidden_dependencies: usize,
+    /// Warnings found while the file was only an import, which are reported if it is added explicitly.
+    pub(crate) import_warnings: Vec<Warning>,
+    /// The size of the file counted towards the total input limit of the compiler.
+    pub(crate) input_len: u64,
 }
 
 impl File {
@@ -117,6 +219,19 @@ impl File {
     /// assert!(File::open("notfound.proto", "notfound.proto".as_ref()).unwrap_err().is_file_not_found());
     /// ```
     pub fn open(name: &str, path: &Path) -> Result<Self, Error> {
+        File::open_with_syntax(name, path, Some(Syntax::Proto2))
+    }
+
+    /// Read a protobuf source file from the filesystem, using `default_syntax` if it does not have a `syntax`
+    /// statement.
+    ///
+    /// If `default_syntax` is `None`, a file without a `syntax` statement is rejected. Otherwise, this behaves
+    /// the same as [`open()`](File::open).
+    pub fn open_with_syntax(
+        name: &str,
+        path: &Path,
+        default_syntax: Option<Syntax>,
+    ) -> Result<Self, Error> {
         let map_io_err = |err: io::Error| -> Error {
             match err.kind() {
                 io::ErrorKind::NotFound => Error::file_not_found(name),
@@ -145,7 +260,7 @@ impl File {
             .read_to_string(&mut buf)
             .map_err(map_io_err)?;
 
-        let descriptor = protox_parse::parse(name, &buf)?;
+        let descriptor = protox_parse::parse_with_default_syntax(name, &buf, default_syntax)?;
 
         Ok(File {
             path: Some(path.to_owned()),
@@ -187,7 +302,30 @@ impl File {
     /// });
     /// ```
     pub fn from_source(name: &str, source: &str) -> Result<Self, Error> {
-        let descriptor = protox_parse::parse(name, source)?;
+        File::from_source_with_syntax(name, source, Some(Syntax::Proto2))
+    }
+
+    /// Read a protobuf source file from a string, using `default_syntax` if it does not have a `syntax` statement.
+    ///
+    /// If `default_syntax` is `None`, a file without a `syntax` statement is rejected. Otherwise, this behaves
+    /// the same as [`from_source()`](File::from_source).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use protox::{file::File, Syntax};
+    /// let file = File::from_source_with_syntax("foo.proto", "message Foo { int32 bar = 1; }", Some(Syntax::Proto3)).unwrap();
+    /// assert_eq!(file.file_descriptor_proto().syntax(), "proto3");
+    ///
+    /// let err = File::from_source_with_syntax("foo.proto", "message Foo {}", None).unwrap_err();
+    /// assert_eq!(err.to_string(), "missing syntax statement");
+    /// ```
+    pub fn from_source_with_syntax(
+        name: &str,
+        source: &str,
+        default_syntax: Option<Syntax>,
+    ) -> Result<Self, Error> {
+        let descriptor = protox_parse::parse_with_default_syntax(name, source, default_syntax)?;
 
         Ok(File {
             path: None,
@@ -200,6 +338,12 @@ impl File {
     /// Create a new instance of [`File`] from a parsed [`FileDescriptorProto`].
     ///
     /// The file does not need to have type names or imports resolved. Typically, it would be returned by the [`parse()`](protox_parse::parse()) method.
+    ///
+    /// Since [`FileDescriptorProto`] does not store unknown fields, any extension options which were already
+    /// interpreted have been lost. Custom options can still be provided in the `uninterpreted_option` field of
+    /// each options message, as [`parse()`](protox_parse::parse()) does, and they are interpreted when the file
+    /// is compiled. To keep interpreted extension options, use
+    /// [`decode_file_descriptor_proto()`](File::decode_file_descriptor_proto) instead.
     pub fn from_file_descriptor_proto(file: prost_types::FileDescriptorProto) -> Self {
         File {
             path: None,
@@ -229,6 +373,75 @@ impl File {
         })
     }
 
+    /// Returns a copy of this file with a different name, without parsing it again.
+    ///
+    /// This is useful when a file should be imported under a different name than it was originally opened with,
+    /// for example when it is vendored. The [`FileDescriptorProto::name`] field is updated, while the
+    /// [path](File::path) and [source](File::source) are unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use protox::file::File;
+    /// let file = File::from_source("foo.proto", "message Foo { }").unwrap();
+    /// let renamed = file.clone_with_name("vendor/foo.proto");
+    /// assert_eq!(renamed.name(), "vendor/foo.proto");
+    /// assert_eq!(renamed.file_descriptor_proto().message_type[0].name(), "Foo");
+    /// ```
+    pub fn clone_with_name(&self, name: &str) -> File {
+        let encoded = self.encoded.as_ref().map(|encoded| {
+            // Fields are merged when decoding, so appending the new name overrides the existing one
+            // while preserving any extension options.
+            let mut buf = encoded.to_vec();
+            prost::encoding::string::encode(1, &name.to_owned(), &mut buf);
+            Bytes::from(buf)
+        });
+
+        File {
+            path: self.path.clone(),
+            source: self.source.clone(),
+            descriptor: FileDescriptorProto {
+                name: Some(name.to_owned()),
+                ..self.descriptor.clone()
+            },
+            encoded,
+        }
+    }
+
+    /// Parses the source of this file again with `default_syntax`, if it does not have a `syntax` statement.
+    ///
+    /// Files are assumed to have been parsed as `proto2`, the default for [`File::open`] and
+    /// [`File::from_source`].
+    fn reparse_with_syntax(self, default_syntax: Option<Syntax>) -> Result<File, Error> {
+        /// The field number of `syntax` in `FileDescriptorProto`.
+        const SYNTAX: i32 = 12;
+
+        let source = match &self.source {
+            Some(source) if default_syntax != Some(Syntax::Proto2) => source,
+            _ => return Ok(self),
+        };
+        let has_syntax = self
+            .descriptor
+            .source_code_info
+            .as_ref()
+            .is_some_and(|info| {
+                info.location
+                    .iter()
+                    .any(|location| location.path == [SYNTAX])
+            });
+        if has_syntax {
+            return Ok(self);
+        }
+
+        let descriptor =
+            protox_parse::parse_with_default_syntax(self.name(), source, default_syntax)?;
+        Ok(File {
+            descriptor,
+            encoded: None,
+            ..self
+        })
+    }
+
     /// Returns the name of this file.
     pub fn name(&self) -> &str {
         self.descriptor.name()
@@ -244,6 +457,29 @@ impl File {
         self.source.as_deref()
     }
 
+    /// Converts a byte range in the source of this file to zero-based `(line, column)` positions.
+    ///
+    /// Columns are byte offsets from the start of the line, matching the spans in
+    /// [`source_code_info`](prost_types::FileDescriptorProto::source_code_info). Returns `None` if the source
+    /// is not available, or if the range is out of bounds.
+    pub fn resolve_span(&self, span: Range<usize>) -> Option<((usize, usize), (usize, usize))> {
+        let source = self.source.as_deref()?;
+        if span.start > span.end || span.end > source.len() {
+            return None;
+        }
+
+        let resolve = |offset: usize| {
+            let before = &source.as_bytes()[..offset];
+            let line = before.iter().filter(|&&b| b == b'\n').count();
+            let line_start = before
+                .iter()
+                .rposition(|&b| b == b'\n')
+                .map_or(0, |index| index + 1);
+            (line, offset - line_start)
+        };
+        Some((resolve(span.start), resolve(span.end)))
+    }
+
     /// Returns the parsed value of the source file.
     ///
     /// This is typically equivalent to calling [`parse()`](protox_parse::parse()) on the string returned by [`source()`](File::source).
@@ -1,14 +1,22 @@
 use std::{
+    cell::Cell,
+    collections::HashMap,
+    fs,
     io::{self, Seek, Write},
     path::{Path, PathBuf},
+    rc::Rc,
 };
 
+use protox_parse::Syntax;
+
+use prost::Message;
 use prost_types::{source_code_info::Location, FileDescriptorProto, SourceCodeInfo};
 
-use crate::{file::FileResolver, Error};
+use crate::{file::FileResolver, Compiler, Error};
 
 use super::{
-    ChainFileResolver, DescriptorSetFileResolver, File, GoogleFileResolver, IncludeFileResolver,
+    CachingFileResolver, ChainFileResolver, DescriptorSetFileResolver, File, GoogleFileResolver,
+    IncludeFileResolver, MapFileResolver,
 };
 
 struct EmptyFileResolver;
@@ -67,7 +75,249 @@ fn chain_file_resolver() {
         .open_file("notfound.proto")
         .unwrap_err()
         .is_file_not_found());
+    assert_eq!(resolver.resolver_index("notfound.proto"), None);
+    assert_eq!(resolver.resolver_index("foo.proto"), None);
+    assert_eq!(resolver.open_file("foo.proto").unwrap().name(), "foo.proto");
+    assert_eq!(resolver.resolver_index("foo.proto"), Some(1));
+    assert_eq!(resolver.open_file("bar.proto").unwrap().name(), "bar.proto");
+    assert_eq!(resolver.resolver_index("bar.proto"), Some(2));
+}
+
+#[test]
+fn chain_file_resolver_strict_uniqueness() {
+    let file = |source| SingleFileResolver(File::from_source("a.proto", source).unwrap());
+
+    let mut resolver = ChainFileResolver::new();
+    resolver.add(file("message Foo {}"));
+    resolver.add(EmptyFileResolver);
+    resolver.add(file("message Foo {}"));
+    resolver.strict_uniqueness(true);
+    assert_eq!(resolver.open_file("a.proto").unwrap().name(), "a.proto");
+
+    resolver.add(file("message Bar {}"));
+    let err = resolver.open_file("a.proto").unwrap_err();
+    assert_eq!(err.file(), Some("a.proto"));
+    assert_eq!(
+        err.to_string(),
+        "file 'a.proto' can be opened from multiple locations with different contents"
+    );
+    assert!(resolver
+        .open_file("notfound.proto")
+        .unwrap_err()
+        .is_file_not_found());
+
+    resolver.strict_uniqueness(false);
+    assert_eq!(
+        resolver
+            .open_file("a.proto")
+            .unwrap()
+            .file_descriptor_proto()
+            .message_type[0]
+            .name(),
+        "Foo"
+    );
+}
+
+#[test]
+fn chain_file_resolver_resolve_and_open_different_resolvers() {
+    struct OpenOnlyFileResolver(File);
+
+    impl FileResolver for OpenOnlyFileResolver {
+        fn open_file(&self, name: &str) -> Result<File, Error> {
+            if name == self.0.name() {
+                Ok(self.0.clone())
+            } else {
+                Err(Error::file_not_found(name))
+            }
+        }
+    }
+
+    let mut resolver = ChainFileResolver::new();
+    resolver.add(IncludeFileResolver::new("/path/to/include".into()));
+    resolver.add(OpenOnlyFileResolver(File {
+        path: Some(PathBuf::from("/other/foo.proto")),
+        source: Some(String::new()),
+        descriptor: protox_parse::parse("foo.proto", "").unwrap(),
+        encoded: None,
+    }));
+
+    // The first resolver resolves the path, but only the second can open the file.
+    assert_eq!(
+        resolver
+            .resolve_path("/path/to/include/foo.proto".as_ref())
+            .as_deref(),
+        Some("foo.proto")
+    );
+    assert_eq!(
+        resolver.open_file("foo.proto").unwrap().path(),
+        Some(Path::new("/other/foo.proto"))
+    );
+
+    // When compiling by path, the file which was opened must match the given path.
+    let err = crate::Compiler::with_file_resolver(resolver)
+        .open_file("/path/to/include/foo.proto")
+        .unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        crate::error::ErrorKind::FileShadowed { name, path, shadow }
+            if name == "foo.proto"
+                && path == Path::new("/path/to/include/foo.proto")
+                && shadow == Path::new("/other/foo.proto")
+    ));
+}
+
+struct VersionedFileResolver {
+    version: Cell<Option<u64>>,
+    opened: Cell<u32>,
+}
+
+impl FileResolver for VersionedFileResolver {
+    fn open_file(&self, name: &str) -> Result<File, Error> {
+        self.opened.set(self.opened.get() + 1);
+        File::from_source(name, "message Foo {}")
+    }
+
+    fn file_version(&self, _name: &str) -> Option<u64> {
+        self.version.get()
+    }
+}
+
+#[test]
+fn caching_file_resolver() {
+    let resolver = CachingFileResolver::new(VersionedFileResolver {
+        version: Cell::new(Some(1)),
+        opened: Cell::new(0),
+    });
+
+    let file = resolver.open_file("foo.proto").unwrap();
+    assert_eq!(file.name(), "foo.proto");
+    assert_eq!(resolver.get_ref().opened.get(), 1);
+
     assert_eq!(resolver.open_file("foo.proto").unwrap().name(), "foo.proto");
+    assert_eq!(resolver.get_ref().opened.get(), 1);
+
+    assert_eq!(resolver.open_file("bar.proto").unwrap().name(), "bar.proto");
+    assert_eq!(resolver.get_ref().opened.get(), 2);
+
+    resolver.get_ref().version.set(Some(2));
+    resolver.open_file("foo.proto").unwrap();
+    assert_eq!(resolver.get_ref().opened.get(), 3);
+    resolver.open_file("foo.proto").unwrap();
+    assert_eq!(resolver.get_ref().opened.get(), 3);
+
+    resolver.get_ref().version.set(None);
+    resolver.open_file("foo.proto").unwrap();
+    resolver.open_file("foo.proto").unwrap();
+    assert_eq!(resolver.get_ref().opened.get(), 5);
+}
+
+struct CountingFileResolver<R> {
+    inner: R,
+    opened: Rc<Cell<u32>>,
+}
+
+impl<R: FileResolver> FileResolver for CountingFileResolver<R> {
+    fn resolve_path(&self, path: &Path) -> Option<String> {
+        self.inner.resolve_path(path)
+    }
+
+    fn open_file(&self, name: &str) -> Result<File, Error> {
+        self.open_file_with_syntax(name, Some(Syntax::Proto2))
+    }
+
+    fn open_file_with_syntax(
+        &self,
+        name: &str,
+        default_syntax: Option<Syntax>,
+    ) -> Result<File, Error> {
+        self.opened.set(self.opened.get() + 1);
+        self.inner.open_file_with_syntax(name, default_syntax)
+    }
+
+    fn file_version(&self, name: &str) -> Option<u64> {
+        self.inner.file_version(name)
+    }
+}
+
+#[test]
+fn caching_include_file_resolver() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("root.proto"),
+        "import 'dep.proto'; import 'google/protobuf/empty.proto';",
+    )
+    .unwrap();
+    fs::write(dir.path().join("dep.proto"), "message Dep {}").unwrap();
+
+    // The same resolvers as `Compiler::new`, with a cache in front.
+    let mut chain = ChainFileResolver::new();
+    chain.add(IncludeFileResolver::new(dir.path().to_owned()));
+    chain.add(GoogleFileResolver::new());
+    let opened = Rc::new(Cell::new(0));
+    let resolver = CachingFileResolver::new(CountingFileResolver {
+        inner: chain,
+        opened: opened.clone(),
+    });
+
+    let mut compiler = Compiler::with_file_resolver(resolver);
+    compiler.open_file(dir.path().join("root.proto")).unwrap();
+    assert_eq!(opened.get(), 3);
+
+    compiler.clear();
+    compiler.open_file(dir.path().join("root.proto")).unwrap();
+    assert_eq!(opened.get(), 3);
+
+    fs::write(dir.path().join("dep.proto"), "message Dep { }").unwrap();
+    compiler.clear();
+    compiler.open_file(dir.path().join("root.proto")).unwrap();
+    assert_eq!(opened.get(), 4);
+    assert!(compiler
+        .descriptor_pool()
+        .get_message_by_name("Dep")
+        .is_some());
+}
+
+#[test]
+fn chain_file_resolver_version_unversioned_first() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("foo.proto"), "message Foo {}").unwrap();
+
+    let mut resolver = ChainFileResolver::new();
+    resolver.add(MapFileResolver::new(HashMap::from([(
+        "foo.proto".to_owned(),
+        "message Bar {}".to_owned(),
+    )])));
+    resolver.add(IncludeFileResolver::new(dir.path().to_owned()));
+
+    // The file is opened from the first resolver, which does not report versions.
+    assert_eq!(resolver.file_version("foo.proto"), None);
+    assert_eq!(resolver.resolver_index("foo.proto"), None);
+    resolver.open_file("foo.proto").unwrap();
+    assert_eq!(resolver.resolver_index("foo.proto"), Some(0));
+
+    fs::write(dir.path().join("bar.proto"), "message Bar {}").unwrap();
+    assert!(resolver.file_version("bar.proto").is_some());
+    assert_eq!(resolver.file_version("notfound.proto"), None);
+}
+
+#[test]
+fn include_file_resolver_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = dir.path().join("first");
+    let second = dir.path().join("second");
+    fs::create_dir(&first).unwrap();
+    fs::create_dir(&second).unwrap();
+    fs::write(second.join("foo.proto"), "message Foo {}").unwrap();
+
+    let resolver = IncludeFileResolver::with_includes([first.clone(), second]);
+    let version = resolver.file_version("foo.proto").unwrap();
+    assert_eq!(resolver.file_version("foo.proto"), Some(version));
+    assert_eq!(resolver.file_version("notfound.proto"), None);
+    assert!(resolver.contains_file("foo.proto"));
+    assert!(!resolver.contains_file("notfound.proto"));
+
+    fs::write(first.join("foo.proto"), "message Foo {}").unwrap();
+    assert_ne!(resolver.file_version("foo.proto"), Some(version));
 }
 
 #[test]
@@ -105,6 +355,63 @@ fn descriptor_set_file_resolver() {
         .is_file_not_found());
 }
 
+#[test]
+fn map_file_resolver() {
+    let resolver = MapFileResolver::new(
+        [("foo/bar.proto".to_owned(), "message Bar {}".to_owned())]
+            .into_iter()
+            .collect(),
+    );
+
+    assert_eq!(
+        resolver.resolve_path(Path::new("foo/bar.proto")).as_deref(),
+        Some("foo/bar.proto")
+    );
+    assert_eq!(resolver.resolve_path(Path::new("foo/baz.proto")), None);
+    assert!(resolver.contains_file("foo/bar.proto"));
+    assert!(!resolver.contains_file("foo/baz.proto"));
+
+    let file = resolver.open_file("foo/bar.proto").unwrap();
+    assert_eq!(file.name(), "foo/bar.proto");
+    assert_eq!(file.source(), Some("message Bar {}"));
+    assert_eq!(file.path(), None);
+    assert_eq!(file.file_descriptor_proto().message_type[0].name(), "Bar");
+
+    assert!(resolver
+        .open_file("notfound.proto")
+        .unwrap_err()
+        .is_file_not_found());
+}
+
+#[test]
+fn descriptor_set_file_resolver_from_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("desc.bin");
+    let set = prost_types::FileDescriptorSet {
+        file: vec![FileDescriptorProto {
+            name: Some("foo.proto".to_owned()),
+            ..Default::default()
+        }],
+    };
+    std::fs::write(&path, set.encode_to_vec()).unwrap();
+
+    let resolver = DescriptorSetFileResolver::from_file(&path).unwrap();
+    assert_eq!(resolver.open_file("foo.proto").unwrap().name(), "foo.proto");
+
+    let err = DescriptorSetFileResolver::from_file(dir.path().join("notfound.bin")).unwrap_err();
+    assert!(err.is_io());
+
+    std::fs::write(&path, [0x0a, 0x05]).unwrap();
+    let err = DescriptorSetFileResolver::from_file(&path).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "file '{}' is not a valid file descriptor set",
+            path.display()
+        )
+    );
+}
+
 #[test]
 fn google_resolver() {
     let resolver = GoogleFileResolver::new();
@@ -279,6 +586,95 @@ fn include_resolver() {
     );
 }
 
+#[test]
+fn include_resolver_multiple_includes() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = dir.path().join("first");
+    let second = dir.path().join("second");
+    std::fs::create_dir(&first).unwrap();
+    std::fs::create_dir(&second).unwrap();
+    std::fs::write(first.join("foo.proto"), "message First {}").unwrap();
+    std::fs::write(second.join("foo.proto"), "message Second {}").unwrap();
+    std::fs::write(second.join("bar.proto"), "message Bar {}").unwrap();
+
+    let include = IncludeFileResolver::with_includes([&first, &second]);
+    assert_eq!(include.include_paths(), [first.clone(), second.clone()]);
+
+    assert_eq!(
+        include.resolve_path(&second.join("foo.proto")).as_deref(),
+        Some("foo.proto")
+    );
+    assert_eq!(include.resolve_path(&dir.path().join("foo.proto")), None);
+
+    let foo = include.open_file("foo.proto").unwrap();
+    assert_eq!(foo.path(), Some(first.join("foo.proto").as_ref()));
+    let bar = include.open_file("bar.proto").unwrap();
+    assert_eq!(bar.path(), Some(second.join("bar.proto").as_ref()));
+    assert!(include
+        .open_file("missing.proto")
+        .unwrap_err()
+        .is_file_not_found());
+
+    let mut compiler = crate::Compiler::with_file_resolver(include);
+    compiler.open_file(first.join("foo.proto")).unwrap();
+    let err = compiler.open_file(second.join("foo.proto")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "path '{}' is shadowed by '{}' in the include paths",
+            second.join("foo.proto").display(),
+            first.join("foo.proto").display()
+        )
+    );
+}
+
+#[test]
+fn list_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = dir.path().join("first");
+    let second = dir.path().join("second");
+    std::fs::create_dir_all(first.join("nested/deeper")).unwrap();
+    std::fs::create_dir(&second).unwrap();
+    std::fs::write(first.join("foo.proto"), "message Foo {}").unwrap();
+    std::fs::write(first.join("nested/deeper/baz.proto"), "").unwrap();
+    std::fs::write(first.join("nested/readme.md"), "").unwrap();
+    std::fs::write(second.join("foo.proto"), "message Shadowed {}").unwrap();
+    std::fs::write(second.join("bar.proto"), "").unwrap();
+
+    let include =
+        IncludeFileResolver::with_includes([&first, &second, &dir.path().join("missing")]);
+    assert_eq!(
+        include.list_files(),
+        Some(vec![
+            "foo.proto".to_owned(),
+            "nested/deeper/baz.proto".to_owned(),
+            "bar.proto".to_owned(),
+        ])
+    );
+
+    let mut chain = ChainFileResolver::new();
+    chain.add(EmptyFileResolver);
+    assert_eq!(chain.list_files(), None);
+    chain.add(MapFileResolver::new(
+        [("bar.proto", ""), ("map.proto", "")]
+            .into_iter()
+            .map(|(name, source)| (name.to_owned(), source.to_owned()))
+            .collect(),
+    ));
+    chain.add(CachingFileResolver::new(include));
+    assert_eq!(
+        chain.list_files(),
+        Some(vec![
+            "bar.proto".to_owned(),
+            "map.proto".to_owned(),
+            "foo.proto".to_owned(),
+            "nested/deeper/baz.proto".to_owned(),
+        ])
+    );
+
+    assert_eq!(GoogleFileResolver::new().list_files(), None);
+}
+
 #[test]
 fn file_open() {
     let mut tempfile = tempfile::NamedTempFile::new().unwrap();
@@ -345,6 +741,32 @@ fn file_from_source() {
     );
 }
 
+#[test]
+fn file_resolve_span() {
+    let source = "syntax = 'proto3';\n\nmessage Foo {\n  int32 a = 1;\n}\n";
+    let file = File::from_source("foo.proto", source).unwrap();
+
+    let start = source.find("message").unwrap();
+    let end = source.rfind('}').unwrap() + 1;
+    assert_eq!(file.resolve_span(start..end), Some(((2, 0), (4, 1))));
+    let start = source.find("int32").unwrap();
+    assert_eq!(file.resolve_span(start..start + 5), Some(((3, 2), (3, 7))));
+    assert_eq!(file.resolve_span(0..source.len()), Some(((0, 0), (5, 0))));
+    assert_eq!(file.resolve_span(0..source.len() + 1), None);
+
+    let locations = &file
+        .file_descriptor_proto()
+        .source_code_info
+        .as_ref()
+        .unwrap()
+        .location;
+    let location = locations.iter().find(|l| l.path == [4, 0]).unwrap();
+    assert_eq!(location.span, [2, 0, 4, 1]);
+
+    let file = File::from_file_descriptor_proto(file.file_descriptor_proto().clone());
+    assert_eq!(file.resolve_span(0..1), None);
+}
+
 #[test]
 fn file_from_file_descriptor_proto() {
     let file = File::from(FileDescriptorProto {
@@ -404,3 +826,225 @@ fn file_decode_file_descriptor_proto_err() {
     let invalid = b"invalid";
     assert!(File::decode_file_descriptor_proto(invalid.as_ref()).is_err());
 }
+
+#[test]
+fn file_clone_with_name() {
+    let file = File::from_source("foo.proto", "message Foo {}").unwrap();
+    let renamed = file.clone_with_name("bar.proto");
+    assert_eq!(file.name(), "foo.proto");
+    assert_eq!(renamed.name(), "bar.proto");
+    assert_eq!(renamed.source(), file.source());
+    assert_eq!(
+        renamed.file_descriptor_proto(),
+        &FileDescriptorProto {
+            name: Some("bar.proto".to_owned()),
+            ..file.file_descriptor_proto().clone()
+        }
+    );
+
+    let encoded = File::decode_file_descriptor_proto(
+        [
+            0x0a, 0x09, 0x66, 0x6f, 0x6f, 0x2e, 0x70, 0x72, 0x6f, 0x74, 0x6f, 0x90, 0x03, 0x05,
+        ]
+        .as_ref(),
+    )
+    .unwrap();
+    let renamed = encoded.clone_with_name("bar.proto");
+    assert_eq!(renamed.name(), "bar.proto");
+    let encoded_renamed = renamed.encoded.unwrap();
+    assert_eq!(
+        FileDescriptorProto::decode(encoded_renamed.as_ref()).unwrap(),
+        FileDescriptorProto {
+            name: Some("bar.proto".to_owned()),
+            ..Default::default()
+        }
+    );
+    assert!(encoded_renamed
+        .as_ref()
+        .starts_with(&encoded.encoded.unwrap()));
+}
+
+#[test]
+#[cfg(feature = "git")]
+fn git_file_resolver() {
+    use std::{fs, process::Command};
+
+    use super::GitFileResolver;
+
+    let git = |dir: &Path, args: &[&str]| {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("source");
+    fs::create_dir_all(source.join("protos/dep")).unwrap();
+    git(&source, &["init", "-q"]);
+
+    fs::write(source.join("protos/root.proto"), "import 'dep/dep.proto';").unwrap();
+    fs::write(source.join("protos/dep/dep.proto"), "message Dep {}").unwrap();
+    fs::write(source.join("other.proto"), "message Other {}").unwrap();
+    git(&source, &["add", "."]);
+    git(&source, &["commit", "-q", "-m", "first"]);
+    git(&source, &["tag", "v1"]);
+
+    fs::write(source.join("protos/dep/dep.proto"), "message Changed {}").unwrap();
+    git(&source, &["commit", "-q", "-a", "-m", "second"]);
+
+    let bare = dir.path().join("bare.git");
+    git(
+        dir.path(),
+        &[
+            "clone",
+            "-q",
+            "--bare",
+            source.to_str().unwrap(),
+            "bare.git",
+        ],
+    );
+
+    let resolver = GitFileResolver::new(&bare, "v1", "protos").unwrap();
+    let file = resolver.open_file("dep/dep.proto").unwrap();
+    assert_eq!(file.source(), Some("message Dep {}"));
+    assert_eq!(file.file_descriptor_proto().message_type[0].name(), "Dep");
+    assert!(resolver.file_version("dep/dep.proto").is_some());
+    assert_eq!(
+        resolver
+            .open_file("root.proto")
+            .unwrap()
+            .file_descriptor_proto()
+            .dependency,
+        vec!["dep/dep.proto".to_owned()]
+    );
+    assert!(resolver
+        .open_file("other.proto")
+        .unwrap_err()
+        .is_file_not_found());
+
+    let resolver = GitFileResolver::new(&bare, "HEAD", "").unwrap();
+    assert_eq!(
+        resolver.open_file("protos/dep/dep.proto").unwrap().source(),
+        Some("message Changed {}")
+    );
+    assert!(resolver.open_file("other.proto").is_ok());
+
+    assert_eq!(
+        GitFileResolver::new(&bare, "v2", "protos")
+            .unwrap_err()
+            .to_string(),
+        "reference 'v2' not found in git repository"
+    );
+    assert_eq!(
+        GitFileResolver::new(&bare, "--output=x", "protos")
+            .unwrap_err()
+            .to_string(),
+        "reference '--output=x' not found in git repository"
+    );
+    assert!(GitFileResolver::new(&bare, "v1", "missing").is_err());
+
+    let clone = dir.path().join("clone.git");
+    let resolver =
+        GitFileResolver::from_url(bare.to_str().unwrap(), &clone, "v1", "protos").unwrap();
+    assert_eq!(
+        resolver.open_file("dep/dep.proto").unwrap().source(),
+        Some("message Dep {}")
+    );
+    assert!(GitFileResolver::new(&clone, "v1", "protos").is_ok());
+
+    let err = GitFileResolver::from_url(
+        "http://127.0.0.1:1/repo.git",
+        dir.path().join("unreachable.git"),
+        "v1",
+        "",
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("failed to fetch git repository 'http://127.0.0.1:1/repo.git'"));
+}
+
+#[test]
+#[cfg(feature = "reqwest")]
+fn http_file_resolver() {
+    use std::{
+        io::{BufRead, BufReader},
+        net::TcpListener,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        thread,
+    };
+
+    use super::HttpFileResolver;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server_requests = requests.clone();
+    let paths = Arc::new(Mutex::new(Vec::new()));
+    let server_paths = paths.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request_line = String::new();
+            BufReader::new(&stream)
+                .read_line(&mut request_line)
+                .unwrap();
+            server_requests.fetch_add(1, Ordering::SeqCst);
+            if let Some(path) = request_line.split(' ').nth(1) {
+                server_paths.lock().unwrap().push(path.to_owned());
+            }
+
+            let (status, body) = match request_line.split(' ').nth(1) {
+                Some("/protos/dep/dep.proto") => ("200 OK", "message Dep {}"),
+                Some("/protos/error.proto") => ("500 Internal Server Error", "oops"),
+                _ => ("404 Not Found", ""),
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+
+    let resolver = HttpFileResolver::new(format!("http://127.0.0.1:{}/protos/", port));
+
+    let file = resolver.open_file("dep/dep.proto").unwrap();
+    assert_eq!(file.name(), "dep/dep.proto");
+    assert_eq!(file.source(), Some("message Dep {}"));
+    assert_eq!(file.path(), None);
+    assert_eq!(resolver.resolve_path(Path::new("dep/dep.proto")), None);
+
+    resolver.open_file("dep/dep.proto").unwrap();
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    assert!(resolver
+        .open_file("missing.proto")
+        .unwrap_err()
+        .is_file_not_found());
+
+    let err = resolver.open_file("error.proto").unwrap_err();
+    assert!(err.is_io());
+    assert!(err.to_string().contains("failed with status 500"));
+
+    // Names are escaped rather than interpreted as part of the URL.
+    assert!(resolver
+        .open_file("dep/dep.proto?x")
+        .unwrap_err()
+        .is_file_not_found());
+    assert_eq!(
+        paths.lock().unwrap().last().map(String::as_str),
+        Some("/protos/dep/dep.proto%3Fx")
+    );
+}
@@ -80,13 +80,23 @@ pub mod file;
 
 mod compile;
 mod error;
+mod method;
+mod strip;
+mod warning;
 
 use std::path::Path;
 
+pub use protox_parse::{
+    ast, Syntax, MAX_MESSAGE_FIELD_NUMBER, MAX_MESSAGE_NESTING_DEPTH,
+    RESERVED_MESSAGE_FIELD_NUMBER_END, RESERVED_MESSAGE_FIELD_NUMBER_START,
+};
 pub use {prost, prost_reflect};
 
 pub use self::compile::Compiler;
-pub use self::error::Error;
+pub use self::error::{Error, Phase};
+pub use self::method::{iter_methods, MethodInfo};
+pub use self::strip::{strip, StripOptions};
+pub use self::warning::{LintLevel, Warning};
 
 /// Compiles a set of protobuf files using the given include paths.
 ///
@@ -191,3 +201,52 @@ pub fn compile(
         .open_files(files)?
         .file_descriptor_set())
 }
+
+/// Parses a single protobuf source file into its syntax tree, without resolving imports or checking it.
+///
+/// This is a convenience wrapper around [`protox_parse::parse_ast`] which returns this crate's [`struct@Error`]
+/// type.
+///
+/// # Examples
+///
+/// ```
+/// # use protox::ast;
+/// let file = protox::parse_ast("foo.proto", "syntax = 'proto3'; message Foo {}").unwrap();
+/// assert_eq!(file.syntax, protox::Syntax::Proto3);
+/// assert!(matches!(&file.items[..], [ast::FileItem::Message(message)] if message.name.value == "Foo"));
+/// ```
+pub fn parse_ast(name: &str, source: &str) -> Result<ast::File, Error> {
+    Ok(protox_parse::parse_ast(name, source)?)
+}
+
+/// Converts a [`FileDescriptorSet`](prost_types::FileDescriptorSet) to its canonical protobuf JSON representation.
+///
+/// This is useful for inspecting compiled descriptors, for example in an editor or when diffing them.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_types::{DescriptorProto, FileDescriptorProto, FileDescriptorSet};
+/// let file_descriptor_set = FileDescriptorSet {
+///     file: vec![FileDescriptorProto {
+///         name: Some("foo.proto".to_owned()),
+///         message_type: vec![DescriptorProto {
+///             name: Some("Foo".to_owned()),
+///             ..Default::default()
+///         }],
+///         ..Default::default()
+///     }],
+/// };
+///
+/// assert_eq!(
+///     protox::to_json(&file_descriptor_set),
+///     r#"{"file":[{"name":"foo.proto","messageType":[{"name":"Foo"}]}]}"#,
+/// );
+/// ```
+#[cfg(feature = "reflect")]
+pub fn to_json(file_descriptor_set: &prost_types::FileDescriptorSet) -> String {
+    use prost_reflect::ReflectMessage;
+
+    serde_json::to_string(&file_descriptor_set.transcode_to_dynamic())
+        .expect("failed to serialize file descriptor set")
+}
@@ -46,6 +46,9 @@ pub fn main() -> Result<()> {
     for file in args.files {
         compiler.open_file(file)?;
     }
+    for warning in compiler.warnings() {
+        eprintln!("{:?}", miette::Report::new(warning.clone()));
+    }
     if let Some(output) = args.output {
         fs::write(output, compiler.encode_file_descriptor_set())
             .map_err(|err| miette::miette!(err))?;
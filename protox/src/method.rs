@@ -0,0 +1,70 @@
+use prost_types::FileDescriptorProto;
+
+/// Information about an RPC method, returned by [`iter_methods()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodInfo {
+    /// The fully-qualified name of the service defining this method, for example `package.Service`.
+    pub service: String,
+    /// The name of the method.
+    pub method: String,
+    /// The fully-qualified name of the input type, as it appears in the descriptor, for example `.package.Request`.
+    pub input_type: String,
+    /// The fully-qualified name of the output type, as it appears in the descriptor, for example `.package.Response`.
+    pub output_type: String,
+    /// Whether the client sends a stream of input messages.
+    pub client_streaming: bool,
+    /// Whether the server returns a stream of output messages.
+    pub server_streaming: bool,
+}
+
+/// Returns an iterator over every RPC method of every service in a file.
+///
+/// Type names are resolved by compilation, so this should be used with files returned from [`Compiler`](crate::Compiler)
+/// rather than directly from [`parse()`](protox_parse::parse()).
+///
+/// # Examples
+///
+/// ```
+/// # use std::fs;
+/// # let tempdir = tempfile::TempDir::new().unwrap();
+/// # std::env::set_current_dir(&tempdir).unwrap();
+/// fs::write("root.proto", "
+///     package pkg;
+///
+///     message Foo { }
+///
+///     service Service {
+///         rpc Method(Foo) returns (stream Foo);
+///     }
+/// ").unwrap();
+///
+/// let file_descriptor_set = protox::compile(["root.proto"], ["."]).unwrap();
+/// let methods: Vec<_> = protox::iter_methods(&file_descriptor_set.file[0]).collect();
+///
+/// assert_eq!(methods, vec![protox::MethodInfo {
+///     service: "pkg.Service".to_owned(),
+///     method: "Method".to_owned(),
+///     input_type: ".pkg.Foo".to_owned(),
+///     output_type: ".pkg.Foo".to_owned(),
+///     client_streaming: false,
+///     server_streaming: true,
+/// }]);
+/// ```
+pub fn iter_methods(file: &FileDescriptorProto) -> impl Iterator<Item = MethodInfo> + '_ {
+    file.service.iter().flat_map(move |service| {
+        let service_name = if file.package().is_empty() {
+            service.name().to_owned()
+        } else {
+            format!("{}.{}", file.package(), service.name())
+        };
+
+        service.method.iter().map(move |method| MethodInfo {
+            service: service_name.clone(),
+            method: method.name().to_owned(),
+            input_type: method.input_type().to_owned(),
+            output_type: method.output_type().to_owned(),
+            client_streaming: method.client_streaming(),
+            server_streaming: method.server_streaming(),
+        })
+    })
+}
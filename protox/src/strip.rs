@@ -0,0 +1,115 @@
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, EnumOptions, FieldDescriptorProto, FieldOptions,
+    FileDescriptorSet, MessageOptions, ServiceDescriptorProto,
+};
+
+/// Options controlling which parts of a [`FileDescriptorSet`] are removed by [`strip()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StripOptions {
+    /// If true, the `source_code_info` field of each file is removed.
+    pub source_info: bool,
+    /// If true, the options of each file and each nested descriptor are removed.
+    ///
+    /// The `map_entry`, `message_set_wire_format`, `packed` and `allow_alias` options are kept, since removing them would change how
+    /// messages are encoded or make the descriptors invalid.
+    pub options: bool,
+}
+
+/// Removes source code info and options from a [`FileDescriptorSet`] in place.
+///
+/// This is useful for reducing the size of a file descriptor set which is only needed at runtime.
+///
+/// # Examples
+///
+/// ```
+/// # use std::fs;
+/// # use protox::{strip, StripOptions};
+/// # let tempdir = tempfile::TempDir::new().unwrap();
+/// # std::env::set_current_dir(&tempdir).unwrap();
+/// fs::write("root.proto", "
+///     option java_package = 'com.example';
+///
+///     message Foo { }
+/// ").unwrap();
+///
+/// let mut file_descriptor_set = protox::compile(["root.proto"], ["."]).unwrap();
+/// strip(&mut file_descriptor_set, StripOptions { source_info: true, options: true });
+///
+/// assert_eq!(file_descriptor_set.file[0].source_code_info, None);
+/// assert_eq!(file_descriptor_set.file[0].options, None);
+/// ```
+pub fn strip(file_descriptor_set: &mut FileDescriptorSet, options: StripOptions) {
+    for file in &mut file_descriptor_set.file {
+        if options.source_info {
+            file.source_code_info = None;
+        }
+
+        if options.options {
+            file.options = None;
+            file.message_type.iter_mut().for_each(strip_message_options);
+            file.enum_type.iter_mut().for_each(strip_enum_options);
+            file.extension.iter_mut().for_each(strip_field_options);
+            file.service.iter_mut().for_each(strip_service_options);
+        }
+    }
+}
+
+fn strip_message_options(message: &mut DescriptorProto) {
+    message.options = message.options.as_ref().and_then(|o| {
+        if o.map_entry.is_none() && o.message_set_wire_format.is_none() {
+            return None;
+        }
+
+        Some(MessageOptions {
+            map_entry: o.map_entry,
+            message_set_wire_format: o.message_set_wire_format,
+            ..Default::default()
+        })
+    });
+
+    message.field.iter_mut().for_each(strip_field_options);
+    message.extension.iter_mut().for_each(strip_field_options);
+    message
+        .nested_type
+        .iter_mut()
+        .for_each(strip_message_options);
+    message.enum_type.iter_mut().for_each(strip_enum_options);
+    for range in &mut message.extension_range {
+        range.options = None;
+    }
+    for oneof in &mut message.oneof_decl {
+        oneof.options = None;
+    }
+}
+
+fn strip_field_options(field: &mut FieldDescriptorProto) {
+    field.options = field
+        .options
+        .as_ref()
+        .and_then(|o| o.packed)
+        .map(|packed| FieldOptions {
+            packed: Some(packed),
+            ..Default::default()
+        });
+}
+
+fn strip_enum_options(enum_: &mut EnumDescriptorProto) {
+    enum_.options = enum_
+        .options
+        .as_ref()
+        .and_then(|o| o.allow_alias)
+        .map(|allow_alias| EnumOptions {
+            allow_alias: Some(allow_alias),
+            ..Default::default()
+        });
+    for value in &mut enum_.value {
+        value.options = None;
+    }
+}
+
+fn strip_service_options(service: &mut ServiceDescriptorProto) {
+    service.options = None;
+    for method in &mut service.method {
+        method.options = None;
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use thiserror::Error;
+
+/// Controls whether an optional lint is checked.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LintLevel {
+    /// The lint is not checked.
+    #[default]
+    Allow,
+    /// The lint is checked, and a [`Warning`] is reported for each violation.
+    Warn,
+}
+
+/// A non-fatal problem found while compiling protobuf files.
+///
+/// Warnings do not prevent a file from being compiled. They can be retrieved after compilation using
+/// [`Compiler::warnings`](crate::Compiler::warnings).
+#[derive(Clone, Diagnostic, Error)]
+#[error(transparent)]
+#[diagnostic(transparent)]
+pub struct Warning {
+    kind: Box<WarningKind>,
+}
+
+#[derive(Clone, Debug, Diagnostic, Error)]
+pub(crate) enum WarningKind {
+    #[error("field '{field}' is part of a cycle of required message fields")]
+    #[diagnostic(
+        severity(Warning),
+        help("a message containing this field can never be fully initialized")
+    )]
+    RecursiveRequiredField {
+        name: String,
+        field: String,
+        #[label("defined here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        source_code: Option<NamedSource<String>>,
+    },
+    #[error("field '{field}' has a 64-bit integer type, but does not set the jstype option")]
+    #[diagnostic(
+        severity(Warning),
+        help("JavaScript numbers cannot represent all 64-bit integers; consider adding '[jstype = JS_STRING]'")
+    )]
+    Int64WithoutJsType {
+        name: String,
+        field: String,
+        #[label("defined here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        source_code: Option<NamedSource<String>>,
+    },
+    #[error("import '{import}' is unused")]
+    #[diagnostic(
+        severity(Warning),
+        help("no definitions from this file are referenced; consider removing the import")
+    )]
+    UnusedImport {
+        name: String,
+        import: String,
+        #[label("imported here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        source_code: Option<NamedSource<String>>,
+    },
+    #[error("import '{import}' not found, and has been replaced with an empty file")]
+    #[diagnostic(severity(Warning))]
+    StubbedImport {
+        name: String,
+        import: String,
+        #[label("imported here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        source_code: Option<NamedSource<String>>,
+    },
+}
+
+impl Warning {
+    /// The file in which this warning occurred.
+    pub fn file(&self) -> &str {
+        match &*self.kind {
+            WarningKind::RecursiveRequiredField { name, .. }
+            | WarningKind::Int64WithoutJsType { name, .. }
+            | WarningKind::UnusedImport { name, .. }
+            | WarningKind::StubbedImport { name, .. } => name,
+        }
+    }
+
+    pub(crate) fn from_kind(kind: WarningKind) -> Self {
+        Warning {
+            kind: Box::new(kind),
+        }
+    }
+}
+
+impl fmt::Debug for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.file(), self)
+    }
+}
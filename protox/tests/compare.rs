@@ -6,7 +6,9 @@ use std::{
 
 use prost::Message;
 use prost_reflect::{DynamicMessage, ReflectMessage};
-use prost_types::{field_descriptor_proto::Type, DescriptorProto, FileDescriptorSet};
+use prost_types::{
+    field_descriptor_proto::Type, DescriptorProto, FieldDescriptorProto, FileDescriptorSet,
+};
 use similar_asserts::assert_serde_eq;
 use tempfile::TempDir;
 
@@ -159,6 +161,25 @@ compare!(reserved_ranges);
 compare!(oneof_group_field);
 compare!(service);
 compare!(option_group_field);
+compare!(boolean_file_options);
+compare!(reserved_numbers);
+compare!(json_name_patterns);
+compare!(package_after_definitions);
+compare!(oneof_ordering_proto2);
+compare!(oneof_ordering_proto3);
+compare!(file_options);
+compare!(message_options);
+compare!(enum_options);
+compare!(service_options);
+compare!(field_options);
+compare!(custom_field_options);
+compare!(custom_enum_value_options);
+compare!(extension_range_options);
+compare!(proto3_packed);
+compare!(oneof_field_options);
+compare!(nested_aggregate_options);
+compare!(oneof_ordering_mixed);
+compare!(java_options);
 
 #[test]
 fn google_protobuf_any() {
@@ -299,3 +320,37 @@ fn google_unittest_well_known_types() {
 fn google_unittest() {
     compare("unittest");
 }
+
+#[test]
+fn fully_qualified_type_names() {
+    fn check_field(field: &FieldDescriptorProto) {
+        for name in [&field.type_name, &field.extendee].into_iter().flatten() {
+            assert!(name.starts_with('.'), "{} is not fully-qualified", name);
+        }
+    }
+
+    for name in [
+        "name_resolution.proto",
+        "multiple_extends.proto",
+        "service.proto",
+        "google/protobuf/unittest.proto",
+    ] {
+        let descriptor = protox::compile(
+            [name],
+            [test_data_dir(), google_proto_dir(), google_src_dir()],
+        )
+        .unwrap();
+
+        for mut file in descriptor.file {
+            file.extension.iter().for_each(check_field);
+            visit_messages(&mut file.message_type, &|message| {
+                message.field.iter().for_each(check_field);
+                message.extension.iter().for_each(check_field);
+            });
+            for method in file.service.iter().flat_map(|service| &service.method) {
+                assert!(method.input_type().starts_with('.'));
+                assert!(method.output_type().starts_with('.'));
+            }
+        }
+    }
+}